@@ -0,0 +1,582 @@
+use rbatis::{rbdc::db::Connection as RbdcConnection, RBatis};
+#[cfg(feature = "mysql")]
+use rbdc_mysql::{driver::MysqlDriver, options::MySqlConnectOptions};
+#[cfg(feature = "postgres")]
+use rbdc_pg::{driver::PgDriver, options::PgConnectOptions};
+#[cfg(feature = "sqlite")]
+use rbdc_sqlite::{SqliteConnectOptions, SqliteDriver};
+use std::{
+	cell::RefCell,
+	collections::{HashMap, HashSet},
+	fmt::Display,
+	future::Future,
+	ops::{Deref, DerefMut},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc, OnceLock, RwLock,
+	},
+};
+
+use tokio::sync::OwnedMutexGuard;
+
+use crate::{
+	grammar::{self, Grammar},
+	retry::RetryPolicy,
+	transaction,
+};
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub use options::{SetupOptions, TlsMode};
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use std::str::FromStr;
+
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+mod options;
+
+pub type Connection = Box<dyn RbdcConnection>;
+
+/// A connection handed out for a query: either a fresh one from the pool, or the one the current [`transaction`](crate::transaction) is running on.
+pub enum ConnectionGuard {
+	Pooled(Connection),
+	Transaction(OwnedMutexGuard<Connection>),
+}
+
+impl Deref for ConnectionGuard {
+	type Target = Connection;
+
+	fn deref(&self) -> &Self::Target {
+		match self {
+			Self::Pooled(conn) => conn,
+			Self::Transaction(conn) => conn,
+		}
+	}
+}
+
+impl DerefMut for ConnectionGuard {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		match self {
+			Self::Pooled(conn) => conn,
+			Self::Transaction(conn) => conn,
+		}
+	}
+}
+
+/// The name of the connection used when none is specified.
+pub const DEFAULT: &str = "default";
+
+static DB_POOLS: OnceLock<RwLock<HashMap<String, Arc<Pool>>>> = OnceLock::new();
+
+tokio::task_local! {
+	/// The connections that have been written to inside the current [`sticky`] scope.
+	static STICKY_WRITES: RefCell<HashSet<String>>;
+}
+
+#[cfg(feature = "testing")]
+tokio::task_local! {
+	/// Pools that replace the registered ones for the current task, installed by [`FakeConnection`](crate::testing::FakeConnection).
+	static FAKES: RefCell<HashMap<String, Arc<Pool>>>;
+}
+
+/// A database pool (and its read replicas), along with the dialect it speaks.
+struct Pool {
+	primary: RBatis,
+	replicas: Vec<RBatis>,
+	next_replica: AtomicUsize,
+	database: Database,
+	retry: RetryPolicy,
+}
+
+impl Pool {
+	/// Picks the pool reads should go to, cycling through the replicas.
+	fn reader(&self) -> &RBatis {
+		if self.replicas.is_empty() {
+			return &self.primary;
+		}
+
+		let index = self.next_replica.fetch_add(1, Ordering::Relaxed);
+		&self.replicas[index % self.replicas.len()]
+	}
+}
+
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+	#[error("The provided database URL is invalid.")]
+	UrlError(#[from] rbatis::Error),
+
+	#[error("The `{0}` database driver is not supported. Make sure its feature is enabled.")]
+	UnsupportedDriver(String),
+
+	#[error("Read replicas must use the same database driver as the primary.")]
+	MismatchedReplica,
+
+	#[error("The {0} database pool has already been initialized.")]
+	AlreadyInitialized(String),
+
+	#[error("The {0} environment variable is not set.")]
+	MissingEnv(&'static str),
+
+	#[error("The {0} environment variable has an invalid value: {1}")]
+	InvalidEnv(&'static str, String),
+
+	#[error("The pool options are invalid: {0}.")]
+	InvalidOptions(&'static str),
+
+	#[error("Failed to connect to the database.")]
+	Connection(#[source] rbatis::Error),
+}
+
+/// Sets up the default database pool. The database driver is picked from the scheme of the URL.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has already been initialized, if the provided database URL is invalid, or if the feature for its driver isn't enabled.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub fn setup(database_url: &str) -> Result<(), SetupError> {
+	setup_named_with_replicas(DEFAULT, database_url, &[])
+}
+
+/// Sets up a named database pool, which models can opt into with the `#[ensemble(connection = "...")]` attribute.
+/// The database driver is picked from the scheme of the URL.
+///
+/// # Errors
+///
+/// Returns an error if a pool with the same name has already been initialized, if the provided database URL is invalid, or if the feature for its driver isn't enabled.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub fn setup_named(name: &str, database_url: &str) -> Result<(), SetupError> {
+	setup_named_with_replicas(name, database_url, &[])
+}
+
+/// Sets up the default database pool, sending reads to the given replicas in turn and writes to the primary.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has already been initialized, if any of the provided database URLs are invalid, or if the replicas don't use the same driver as the primary.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub fn setup_with_replicas(database_url: &str, replica_urls: &[&str]) -> Result<(), SetupError> {
+	setup_named_with_replicas(DEFAULT, database_url, replica_urls)
+}
+
+/// Sets up a named database pool, sending reads to the given replicas in turn and writes to the primary.
+///
+/// # Errors
+///
+/// Returns an error if a pool with the same name has already been initialized, if any of the provided database URLs are invalid, or if the replicas don't use the same driver as the primary.
+///
+/// # Panics
+///
+/// Panics if another thread panicked while registering a pool.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub fn setup_named_with_replicas(
+	name: &str,
+	database_url: &str,
+	replica_urls: &[&str],
+) -> Result<(), SetupError> {
+	let options = replica_urls
+		.iter()
+		.fold(SetupOptions::new(database_url), |options, url| {
+			options.replica(url)
+		});
+
+	register(name, build(name, &options)?)
+}
+
+/// Sets up the default database pool with the given options.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has already been initialized, if any of the database URLs are invalid,
+/// if the replicas don't use the same driver as the primary, if the options can't be combined, or if the pool connects eagerly and can't reach the database.
+///
+/// # Panics
+///
+/// Panics if another thread panicked while registering a pool.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub async fn setup_with(options: SetupOptions) -> Result<(), SetupError> {
+	setup_named_with(DEFAULT, options).await
+}
+
+/// Sets up a named database pool with the given options.
+///
+/// # Errors
+///
+/// Returns an error if a pool with the same name has already been initialized, if any of the database URLs are invalid,
+/// if the replicas don't use the same driver as the primary, if the options can't be combined, or if the pool connects eagerly and can't reach the database.
+///
+/// # Panics
+///
+/// Panics if another thread panicked while registering a pool.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub async fn setup_named_with(name: &str, options: SetupOptions) -> Result<(), SetupError> {
+	options.validate()?;
+	let pool = build(name, &options)?;
+
+	let count = options.setup_connections();
+	if count > 0 {
+		for rb in std::iter::once(&pool.primary).chain(&pool.replicas) {
+			tracing::debug!(connection = name, count, "Opening database connections...");
+
+			let connections = futures::future::try_join_all(
+				(0..count).map(|_| async { rb.get_pool()?.get().await }),
+			)
+			.await
+			.map_err(SetupError::Connection)?;
+
+			drop(connections);
+		}
+	}
+
+	register(name, pool)
+}
+
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+fn build(name: &str, options: &SetupOptions) -> Result<Pool, SetupError> {
+	if pool(name).is_some() {
+		return Err(SetupError::AlreadyInitialized(name.to_string()));
+	}
+
+	let database = Database::from_url(&options.url)?;
+	let primary = connect(name, database, &options.url, options)?;
+	let replicas = options
+		.replicas
+		.iter()
+		.map(|url| {
+			if Database::from_url(url)? != database {
+				return Err(SetupError::MismatchedReplica);
+			}
+
+			connect(name, database, url, options)
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(Pool {
+		primary,
+		replicas,
+		database,
+		retry: options.retry.clone(),
+		next_replica: AtomicUsize::new(0),
+	})
+}
+
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+fn register(name: &str, pool: Pool) -> Result<(), SetupError> {
+	let mut pools = DB_POOLS.get_or_init(Default::default).write().unwrap();
+	if pools.contains_key(name) {
+		return Err(SetupError::AlreadyInitialized(name.to_string()));
+	}
+
+	pools.insert(name.to_string(), Arc::new(pool));
+	drop(pools);
+
+	Ok(())
+}
+
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+fn connect(
+	name: &str,
+	database: Database,
+	database_url: &str,
+	options: &SetupOptions,
+) -> Result<RBatis, SetupError> {
+	let rb = RBatis::new();
+
+	match database {
+		#[cfg(feature = "mysql")]
+		Database::MySQL => {
+			tracing::info!(
+				connection = name,
+				database_url = database_url,
+				"Setting up MySQL database pool..."
+			);
+
+			let mut connect_options = MySqlConnectOptions::from_str(database_url)?;
+			if let Some(tls) = options.tls {
+				connect_options = connect_options.ssl_mode(tls.into());
+			}
+
+			rb.init_pool(options.build_pool(MysqlDriver {}, connect_options))?;
+		},
+		#[cfg(feature = "postgres")]
+		Database::PostgreSQL => {
+			tracing::info!(
+				connection = name,
+				database_url = database_url,
+				"Setting up PostgreSQL database pool..."
+			);
+
+			let mut connect_options = PgConnectOptions::from_str(database_url)?;
+			if let Some(tls) = options.tls {
+				connect_options = connect_options.ssl_mode(tls.into());
+			}
+
+			rb.init_pool(options.build_pool(PgDriver {}, connect_options))?;
+		},
+		#[cfg(feature = "sqlite")]
+		Database::SQLite => {
+			tracing::info!(
+				connection = name,
+				database_url = database_url,
+				"Setting up SQLite database pool..."
+			);
+
+			rb.init_pool(options.build_pool(
+				SqliteDriver {},
+				SqliteConnectOptions::from_str(database_url)?,
+			))?;
+		},
+		#[allow(unreachable_patterns)]
+		_ => return Err(SetupError::UnsupportedDriver(database.to_string())),
+	}
+
+	Ok(rb)
+}
+
+fn pool(name: &str) -> Option<Arc<Pool>> {
+	#[cfg(feature = "testing")]
+	if let Ok(Some(pool)) = FAKES.try_with(|fakes| fakes.borrow().get(name).cloned()) {
+		return Some(pool);
+	}
+
+	DB_POOLS.get()?.read().unwrap().get(name).cloned()
+}
+
+/// Runs the given future with the named connection served by the given pool instead of the registered one.
+/// Only affects the current task.
+#[cfg(feature = "testing")]
+pub async fn with_fake<F: Future>(
+	name: &str,
+	database: Database,
+	rb: RBatis,
+	future: F,
+) -> F::Output {
+	let mut fakes = FAKES
+		.try_with(|fakes| fakes.borrow().clone())
+		.unwrap_or_default();
+
+	fakes.insert(
+		name.to_string(),
+		Arc::new(Pool {
+			database,
+			primary: rb,
+			replicas: vec![],
+			retry: RetryPolicy::never(),
+			next_replica: AtomicUsize::new(0),
+		}),
+	);
+
+	FAKES.scope(RefCell::new(fakes), future).await
+}
+
+/// Runs the given future with sticky writes: once it writes to a connection, any reads it makes on that connection
+/// go to the primary instead of a replica, so it always sees its own changes.
+pub async fn sticky<F: Future>(future: F) -> F::Output {
+	STICKY_WRITES.scope(RefCell::default(), future).await
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+	#[error("The {0} database pool has not been initialized.")]
+	NotInitialized(String),
+
+	#[error("An error occurred while connecting to the database.")]
+	Connection(#[from] rbatis::Error),
+}
+
+/// Returns a connection to the primary of the named database, or the connection of the transaction
+/// the current task has open on it. Used internally by `ensemble` models.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has not been initialized, or if an error occurs while connecting to the database.
+pub async fn get(name: &str) -> Result<ConnectionGuard, ConnectError> {
+	if let Some(conn) = transaction::connection(name).await {
+		return Ok(conn);
+	}
+
+	Ok(ConnectionGuard::Pooled(get_pooled(name).await?))
+}
+
+/// Returns a fresh connection to the primary of the named database, ignoring any open transaction.
+pub async fn get_pooled(name: &str) -> Result<Connection, ConnectError> {
+	match pool(name) {
+		None => Err(ConnectError::NotInitialized(name.to_string())),
+		Some(pool) => Ok(pool.primary.get_pool()?.get().await?),
+	}
+}
+
+/// Returns a connection to read from the named database. This will be one of its replicas, unless there are none,
+/// the current [`sticky`] scope has already written to it, or the current task has a transaction open on it.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has not been initialized, or if an error occurs while connecting to the database.
+pub async fn get_read(name: &str) -> Result<ConnectionGuard, ConnectError> {
+	if let Some(conn) = transaction::connection(name).await {
+		return Ok(conn);
+	}
+
+	let Some(pool) = pool(name) else {
+		return Err(ConnectError::NotInitialized(name.to_string()));
+	};
+
+	let wrote = STICKY_WRITES
+		.try_with(|writes| writes.borrow().contains(name))
+		.unwrap_or(false);
+
+	let rb = if wrote { &pool.primary } else { pool.reader() };
+
+	Ok(ConnectionGuard::Pooled(rb.get_pool()?.get().await?))
+}
+
+/// Records that the current task wrote to the named database, so later reads in the same [`sticky`] scope go to the primary.
+pub fn record_write(name: &str) {
+	let _ = STICKY_WRITES.try_with(|writes| writes.borrow_mut().insert(name.to_string()));
+}
+
+/// A snapshot of a database pool's connections, for health checks and metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct PoolStats {
+	/// The maximum number of connections the pool will open.
+	pub max_connections: u64,
+	/// The number of open connections, both in use and idle.
+	pub connections: u64,
+	/// The number of connections currently in use.
+	pub in_use: u64,
+	/// The number of idle connections.
+	pub idle: u64,
+	/// The total number of times a task had to wait for a connection, as counted by the pool.
+	/// Unlike the other fields, this isn't the number of tasks waiting right now.
+	pub wait_count: u64,
+}
+
+/// Returns the connection statistics of the primary of the named database pool.
+///
+/// # Errors
+///
+/// Returns an error if the database pool has not been initialized.
+pub async fn pool_stats(name: &str) -> Result<PoolStats, ConnectError> {
+	let Some(pool) = pool(name) else {
+		return Err(ConnectError::NotInitialized(name.to_string()));
+	};
+
+	let state = pool.primary.get_pool()?.state().await;
+	let stat = |key: &str| state[key].as_u64().unwrap_or_default();
+
+	Ok(PoolStats {
+		idle: stat("idle"),
+		in_use: stat("in_use"),
+		wait_count: stat("wait_count"),
+		max_connections: stat("max_open"),
+		connections: stat("connections"),
+	})
+}
+
+/// Returns the policy used to retry transient failures on the named database, if its pool has been initialized.
+pub fn retry_policy(name: &str) -> Option<RetryPolicy> {
+	pool(name).map(|pool| pool.retry.clone())
+}
+
+/// Returns the grammar for the named database.
+///
+/// # Panics
+///
+/// Panics if the database pool has not been initialized.
+pub fn grammar(name: &str) -> &'static dyn Grammar {
+	which_db(name).grammar()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+	not(any(feature = "mysql", feature = "postgres", feature = "sqlite")),
+	allow(dead_code)
+)]
+pub enum Database {
+	MySQL,
+	PostgreSQL,
+	SQLite,
+}
+
+#[allow(dead_code)]
+impl Database {
+	pub const fn is_mysql(self) -> bool {
+		matches!(self, Self::MySQL)
+	}
+
+	pub const fn is_postgres(self) -> bool {
+		matches!(self, Self::PostgreSQL)
+	}
+
+	pub const fn is_sqlite(self) -> bool {
+		matches!(self, Self::SQLite)
+	}
+
+	/// Returns the grammar used to build SQL for this database.
+	pub fn grammar(self) -> &'static dyn Grammar {
+		match self {
+			Self::MySQL => &grammar::MySQL,
+			Self::PostgreSQL => &grammar::Postgres,
+			Self::SQLite => &grammar::SQLite,
+		}
+	}
+
+	/// Picks the database from the scheme of a connection URL.
+	#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+	fn from_url(database_url: &str) -> Result<Self, SetupError> {
+		let scheme = database_url
+			.split_once(':')
+			.map_or(database_url, |(scheme, _)| scheme);
+
+		match scheme {
+			"mysql" => Ok(Self::MySQL),
+			"postgres" | "postgresql" => Ok(Self::PostgreSQL),
+			"sqlite" => Ok(Self::SQLite),
+			_ => Err(SetupError::UnsupportedDriver(scheme.to_string())),
+		}
+	}
+}
+
+impl Display for Database {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MySQL => f.write_str("mysql"),
+			Self::PostgreSQL => f.write_str("postgres"),
+			Self::SQLite => f.write_str("sqlite"),
+		}
+	}
+}
+
+/// Returns the database the named pool was set up with.
+///
+/// # Panics
+///
+/// Panics if the database pool has not been initialized.
+pub fn which_db(name: &str) -> Database {
+	pool(name)
+		.unwrap_or_else(|| panic!("The {name} database pool has not been initialized."))
+		.database
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn picks_the_database_from_the_url_scheme() {
+		assert_eq!(
+			Database::from_url("mysql://root@localhost/app").unwrap(),
+			Database::MySQL
+		);
+		assert_eq!(
+			Database::from_url("postgresql://localhost/app").unwrap(),
+			Database::PostgreSQL
+		);
+		assert_eq!(
+			Database::from_url("sqlite::memory:").unwrap(),
+			Database::SQLite
+		);
+		assert!(matches!(
+			Database::from_url("mssql://localhost/app"),
+			Err(SetupError::UnsupportedDriver(scheme)) if scheme == "mssql"
+		));
+	}
+}
@@ -0,0 +1,388 @@
+use rbatis::rbdc::{
+	db::{ConnectOptions, Driver},
+	pool::conn_manager::ConnManager,
+};
+use rbdc_pool_mobc::MobcPool;
+use std::{env, str::FromStr, time::Duration};
+
+use super::SetupError;
+use crate::retry::RetryPolicy;
+
+/// Whether (and how strictly) connections to the database should use TLS. Ignored by `SQLite`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsMode {
+	/// Never use TLS.
+	Disable,
+	/// Use TLS if the server supports it.
+	Prefer,
+	/// Always use TLS, without verifying the server's certificate.
+	Require,
+	/// Always use TLS, verifying the server's certificate against the trusted CAs.
+	VerifyCa,
+	/// Always use TLS, verifying the server's certificate and that its hostname matches.
+	VerifyFull,
+}
+
+impl FromStr for TlsMode {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value.to_ascii_lowercase().as_str() {
+			"disable" | "disabled" => Ok(Self::Disable),
+			"prefer" | "preferred" => Ok(Self::Prefer),
+			"require" | "required" => Ok(Self::Require),
+			"verify-ca" | "verify_ca" => Ok(Self::VerifyCa),
+			"verify-full" | "verify_full" | "verify-identity" | "verify_identity" => {
+				Ok(Self::VerifyFull)
+			},
+			_ => Err(value.to_string()),
+		}
+	}
+}
+
+#[cfg(feature = "mysql")]
+impl From<TlsMode> for rbdc_mysql::options::MySqlSslMode {
+	fn from(mode: TlsMode) -> Self {
+		match mode {
+			TlsMode::Disable => Self::Disabled,
+			TlsMode::Prefer => Self::Preferred,
+			TlsMode::Require => Self::Required,
+			TlsMode::VerifyCa => Self::VerifyCa,
+			TlsMode::VerifyFull => Self::VerifyIdentity,
+		}
+	}
+}
+
+#[cfg(feature = "postgres")]
+impl From<TlsMode> for rbdc_pg::options::PgSslMode {
+	fn from(mode: TlsMode) -> Self {
+		match mode {
+			TlsMode::Disable => Self::Disable,
+			TlsMode::Prefer => Self::Prefer,
+			TlsMode::Require => Self::Require,
+			TlsMode::VerifyCa => Self::VerifyCa,
+			TlsMode::VerifyFull => Self::VerifyFull,
+		}
+	}
+}
+
+/// Configuration for a database pool, passed to [`setup_with`](crate::setup_with).
+///
+/// Settings that aren't specified keep the pool's defaults: at most 10 connections, a 30 second acquire timeout,
+/// and connections that never expire.
+#[derive(Debug, Clone)]
+pub struct SetupOptions {
+	pub(crate) url: String,
+	pub(crate) replicas: Vec<String>,
+	pub(crate) lazy: bool,
+	pub(crate) tls: Option<TlsMode>,
+	pub(crate) retry: RetryPolicy,
+	max_connections: Option<u64>,
+	min_connections: Option<u64>,
+	max_idle_connections: Option<u64>,
+	acquire_timeout: Option<Duration>,
+	idle_timeout: Option<Duration>,
+	max_lifetime: Option<Duration>,
+}
+
+impl SetupOptions {
+	/// Creates the options for a pool connecting to the given database URL.
+	#[must_use]
+	pub fn new(database_url: &str) -> Self {
+		Self {
+			lazy: true,
+			tls: None,
+			replicas: vec![],
+			retry: RetryPolicy::default(),
+			max_connections: None,
+			min_connections: None,
+			max_idle_connections: None,
+			acquire_timeout: None,
+			idle_timeout: None,
+			max_lifetime: None,
+			url: database_url.to_string(),
+		}
+	}
+
+	/// Reads the options from the environment.
+	///
+	/// `DATABASE_URL` is required. The following variables are optional:
+	/// - `DATABASE_REPLICA_URLS`: a comma-separated list of read replicas.
+	/// - `DB_POOL_MAX`, `DB_POOL_MIN` and `DB_POOL_MAX_IDLE`: the maximum and minimum number of connections, and the maximum number of idle connections.
+	/// - `DB_POOL_ACQUIRE_TIMEOUT`, `DB_POOL_IDLE_TIMEOUT` and `DB_POOL_MAX_LIFETIME`: durations, in seconds. An idle timeout or max lifetime of 0 disables it.
+	/// - `DB_TLS_MODE`: one of `disable`, `prefer`, `require`, `verify-ca` or `verify-full`.
+	/// - `DB_POOL_LAZY`: `true` or `false`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `DATABASE_URL` is missing, if any of the variables can't be parsed, or if the options can't be combined (see [`min_connections`](Self::min_connections)).
+	pub fn from_env() -> Result<Self, SetupError> {
+		let url = env::var("DATABASE_URL").map_err(|_| SetupError::MissingEnv("DATABASE_URL"))?;
+		let mut options = Self::new(&url);
+
+		if let Ok(replicas) = env::var("DATABASE_REPLICA_URLS") {
+			options.replicas = replicas
+				.split(',')
+				.map(str::trim)
+				.filter(|url| !url.is_empty())
+				.map(ToString::to_string)
+				.collect();
+		}
+
+		options.max_connections = parse_env("DB_POOL_MAX")?;
+		options.min_connections = parse_env("DB_POOL_MIN")?;
+		options.max_idle_connections = parse_env("DB_POOL_MAX_IDLE")?;
+		options.acquire_timeout = parse_env("DB_POOL_ACQUIRE_TIMEOUT")?.map(Duration::from_secs);
+		options.idle_timeout = parse_env("DB_POOL_IDLE_TIMEOUT")?.map(Duration::from_secs);
+		options.max_lifetime = parse_env("DB_POOL_MAX_LIFETIME")?.map(Duration::from_secs);
+		options.tls = parse_env("DB_TLS_MODE")?;
+		options.lazy = parse_env("DB_POOL_LAZY")?.unwrap_or(true);
+
+		options.validate()?;
+
+		Ok(options)
+	}
+
+	/// Adds a read replica. See [`setup_with_replicas`](crate::setup_with_replicas) for how reads are routed.
+	#[must_use]
+	pub fn replica(mut self, database_url: &str) -> Self {
+		self.replicas.push(database_url.to_string());
+		self
+	}
+
+	/// Sets the maximum number of connections the pool will open.
+	#[must_use]
+	pub const fn max_connections(mut self, max: u64) -> Self {
+		self.max_connections = Some(max);
+		self
+	}
+
+	/// Sets the number of connections the pool keeps open, even while they're idle. They are opened during setup (even if the pool is lazy),
+	/// and the idle cap is raised to fit them if needed.
+	///
+	/// The pool can't reopen connections it closes, so this can't be combined with an [`idle_timeout`](Self::idle_timeout) or
+	/// [`max_lifetime`](Self::max_lifetime), and can't be more than [`max_connections`](Self::max_connections). Setup fails if it is.
+	#[must_use]
+	pub const fn min_connections(mut self, min: u64) -> Self {
+		self.min_connections = Some(min);
+		self
+	}
+
+	/// Sets the maximum number of idle connections the pool keeps open for reuse (2 by default, and 0 for no limit besides [`max_connections`](Self::max_connections)).
+	/// Connections returned to the pool beyond this are closed. When connecting eagerly, this many connections are opened during setup.
+	///
+	/// This is a cap, not a minimum: idle connections can still be closed by the [`idle_timeout`](Self::idle_timeout) and [`max_lifetime`](Self::max_lifetime).
+	/// Use [`min_connections`](Self::min_connections) to keep connections open.
+	#[must_use]
+	pub const fn max_idle_connections(mut self, max: u64) -> Self {
+		self.max_idle_connections = Some(max);
+		self
+	}
+
+	/// Sets how long to wait for a connection to become available before giving up.
+	#[must_use]
+	pub const fn acquire_timeout(mut self, timeout: Duration) -> Self {
+		self.acquire_timeout = Some(timeout);
+		self
+	}
+
+	/// Sets how long a connection may sit idle in the pool before it is closed. A zero duration disables the timeout.
+	#[must_use]
+	pub const fn idle_timeout(mut self, timeout: Duration) -> Self {
+		self.idle_timeout = Some(timeout);
+		self
+	}
+
+	/// Sets how long a connection may be reused before it is closed. A zero duration disables the limit.
+	#[must_use]
+	pub const fn max_lifetime(mut self, lifetime: Duration) -> Self {
+		self.max_lifetime = Some(lifetime);
+		self
+	}
+
+	/// Sets whether connections should use TLS. If not set, the mode from the database URL (if any) is used.
+	#[must_use]
+	pub const fn tls(mut self, mode: TlsMode) -> Self {
+		self.tls = Some(mode);
+		self
+	}
+
+	/// Sets whether the pool should wait until the first query to connect (the default),
+	/// or connect during setup, failing early if the database can't be reached.
+	#[must_use]
+	pub const fn lazy(mut self, lazy: bool) -> Self {
+		self.lazy = lazy;
+		self
+	}
+
+	/// Sets the policy used to retry reads (and transactions run with [`retry_transaction`](crate::retry_transaction)) that fail with transient errors.
+	#[must_use]
+	pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+		self.retry = policy;
+		self
+	}
+
+	/// Checks that the options can be combined.
+	pub(crate) fn validate(&self) -> Result<(), SetupError> {
+		let min = self.min_connections.unwrap_or_default();
+		if min == 0 {
+			return Ok(());
+		}
+
+		if self.max_connections.is_some_and(|max| max > 0 && min > max) {
+			return Err(SetupError::InvalidOptions(
+				"min_connections can't be more than max_connections",
+			));
+		}
+
+		if non_zero(self.idle_timeout).is_some() || non_zero(self.max_lifetime).is_some() {
+			return Err(SetupError::InvalidOptions(
+				"min_connections can't be combined with an idle_timeout or max_lifetime, since closed connections aren't reopened",
+			));
+		}
+
+		Ok(())
+	}
+
+	/// The number of connections to open during setup, which stay in the pool as idle connections. Lazy pools only open their minimum.
+	pub(crate) fn setup_connections(&self) -> u64 {
+		if self.lazy {
+			return self.min_connections.unwrap_or_default();
+		}
+
+		self.eager_connections()
+	}
+
+	/// The number of connections to open during setup when connecting eagerly, which stay in the pool as idle connections.
+	pub(crate) fn eager_connections(&self) -> u64 {
+		let count = self
+			.max_idle_connections
+			.unwrap_or(1)
+			.max(1)
+			.max(self.min_connections.unwrap_or_default());
+
+		// opening more connections than the pool allows would wait for one to be released
+		self.max_connections
+			.filter(|max| *max > 0)
+			.map_or(count, |max| count.min(max))
+	}
+
+	/// Builds a pool for the given driver with these options.
+	pub(crate) fn build_pool<D: Driver + 'static, O: ConnectOptions>(
+		&self,
+		driver: D,
+		connect_options: O,
+	) -> MobcPool {
+		let manager = ConnManager::new_opt_box(Box::new(driver), Box::new(connect_options));
+
+		let mut builder = mobc::Pool::builder();
+		if let Some(max) = self.max_connections {
+			builder = builder.max_open(max);
+		}
+		// the minimum connections sit idle, so they'd be closed if the idle cap didn't fit them
+		let min = self.min_connections.unwrap_or_default();
+		match self.max_idle_connections {
+			Some(0) => builder = builder.max_idle(0),
+			Some(max) => builder = builder.max_idle(max.max(min)),
+			None if min > DEFAULT_MAX_IDLE => builder = builder.max_idle(min),
+			None => {},
+		}
+		if let Some(timeout) = self.acquire_timeout {
+			builder = builder.get_timeout(Some(timeout));
+		}
+
+		MobcPool {
+			manager: manager.clone().into(),
+			inner: builder
+				.max_idle_lifetime(non_zero(self.idle_timeout))
+				.max_lifetime(non_zero(self.max_lifetime))
+				.build(manager.into()),
+		}
+	}
+}
+
+/// The number of idle connections the pool keeps when no cap is set.
+const DEFAULT_MAX_IDLE: u64 = 2;
+
+/// The pool panics on zero durations, which are treated as no limit instead.
+fn non_zero(duration: Option<Duration>) -> Option<Duration> {
+	duration.filter(|duration| !duration.is_zero())
+}
+
+fn parse_env<T: FromStr>(name: &'static str) -> Result<Option<T>, SetupError> {
+	let Ok(value) = env::var(name) else {
+		return Ok(None);
+	};
+
+	value
+		.parse()
+		.map(Some)
+		.map_err(|_| SetupError::InvalidEnv(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_tls_modes() {
+		assert_eq!("require".parse(), Ok(TlsMode::Require));
+		assert_eq!("VERIFY-FULL".parse(), Ok(TlsMode::VerifyFull));
+		assert_eq!("verify_identity".parse(), Ok(TlsMode::VerifyFull));
+		assert_eq!("sometimes".parse::<TlsMode>(), Err("sometimes".to_string()));
+	}
+
+	#[test]
+	fn opens_the_idle_connections_eagerly_within_the_maximum() {
+		let options = SetupOptions::new("sqlite::memory:");
+		assert_eq!(options.eager_connections(), 1);
+		assert_eq!(
+			options.clone().max_idle_connections(5).eager_connections(),
+			5
+		);
+		assert_eq!(
+			options
+				.max_idle_connections(5)
+				.max_connections(3)
+				.eager_connections(),
+			3
+		);
+	}
+
+	#[test]
+	fn opens_the_minimum_connections_even_when_lazy() {
+		let options = SetupOptions::new("sqlite::memory:");
+		assert_eq!(options.setup_connections(), 0);
+		assert_eq!(options.clone().min_connections(3).setup_connections(), 3);
+		assert_eq!(
+			options
+				.lazy(false)
+				.max_idle_connections(2)
+				.min_connections(3)
+				.setup_connections(),
+			3
+		);
+	}
+
+	#[test]
+	fn rejects_minimum_connections_the_pool_would_close() {
+		let options = SetupOptions::new("sqlite::memory:").min_connections(3);
+		assert!(options.validate().is_ok());
+		assert!(options.clone().max_connections(2).validate().is_err());
+		assert!(options
+			.clone()
+			.idle_timeout(Duration::from_secs(30))
+			.validate()
+			.is_err());
+		assert!(options.max_lifetime(Duration::ZERO).validate().is_ok());
+	}
+
+	#[test]
+	fn treats_zero_durations_as_no_limit() {
+		assert_eq!(non_zero(Some(Duration::ZERO)), None);
+		assert_eq!(
+			non_zero(Some(Duration::from_secs(5))),
+			Some(Duration::from_secs(5))
+		);
+	}
+}
@@ -0,0 +1,366 @@
+use rbs::Value;
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+	retry::{self, SERIALIZATION_FAILURE},
+	Error,
+};
+
+/// What the database reported about a failed query, along with the query itself.
+#[derive(Debug, Clone)]
+pub struct DatabaseError {
+	/// The SQLSTATE code of the error, if known.
+	///
+	/// `SQLite` doesn't report SQLSTATE codes, so the standard code for the kind of error is used instead.
+	pub sqlstate: Option<String>,
+	/// The message reported by the database.
+	pub message: String,
+	/// The SQL that failed.
+	pub sql: String,
+	/// The values bound to the query.
+	pub bindings: Vec<Value>,
+}
+
+impl Display for DatabaseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+/// Turns the error a query failed with into the matching [`Error`] variant, based on the SQLSTATE code
+/// and driver-specific error numbers (or, for `SQLite`, the message). Errors that don't match any variant are returned as [`Error::Query`].
+pub fn classify(error: &rbatis::Error, sql: &str, bindings: &[Value]) -> Error {
+	let raw = error.to_string();
+
+	let classified = postgres_fields(&raw).map_or_else(
+		|| mysql(&raw).or_else(|| sqlite(&raw)),
+		|fields| postgres(&fields),
+	);
+
+	let (kind, sqlstate, message) = classified.unwrap_or_else(|| unclassified(&raw));
+
+	let details = Box::new(DatabaseError {
+		sqlstate,
+		message,
+		sql: sql.to_string(),
+		bindings: bindings.to_vec(),
+	});
+
+	match kind {
+		Kind::Unique {
+			constraint,
+			columns,
+		} => Error::UniqueViolation {
+			constraint,
+			columns,
+			details,
+		},
+		Kind::ForeignKey { constraint } => Error::ForeignKeyViolation {
+			constraint,
+			details,
+		},
+		Kind::NotNull { column } => Error::NotNullViolation { column, details },
+		Kind::Check { constraint } => Error::CheckViolation {
+			constraint,
+			details,
+		},
+		Kind::Deadlock => Error::Deadlock { details },
+		Kind::ConnectionLost => Error::ConnectionLost { details },
+		Kind::Other => Error::Query { details },
+	}
+}
+
+enum Kind {
+	Unique {
+		constraint: Option<String>,
+		columns: Vec<String>,
+	},
+	ForeignKey {
+		constraint: Option<String>,
+	},
+	NotNull {
+		column: Option<String>,
+	},
+	Check {
+		constraint: Option<String>,
+	},
+	Deadlock,
+	ConnectionLost,
+	Other,
+}
+
+type Classified = (Kind, Option<String>, String);
+
+/// Errors no driver recognized are only told apart by whether the connection was lost.
+fn unclassified(raw: &str) -> Classified {
+	let sqlstate = retry::sqlstate(raw);
+	let kind = if retry::is_connection_error(sqlstate, raw) {
+		Kind::ConnectionLost
+	} else {
+		Kind::Other
+	};
+
+	(kind, sqlstate.map(ToString::to_string), raw.to_string())
+}
+
+/// Reads the fields of a `PostgreSQL` error, which are reported as the debug representation of the raw response.
+fn postgres_fields(message: &str) -> Option<HashMap<u8, String>> {
+	let (_, storage) = message.split_once("storage: b\"")?;
+
+	let mut bytes = Vec::new();
+	let mut chars = storage.bytes();
+	while let Some(byte) = chars.next() {
+		match byte {
+			b'"' => break,
+			b'\\' => match chars.next()? {
+				b'0' => bytes.push(0),
+				b'n' => bytes.push(b'\n'),
+				b'r' => bytes.push(b'\r'),
+				b't' => bytes.push(b'\t'),
+				b'x' => {
+					let hex = [chars.next()?, chars.next()?];
+					bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+				},
+				escaped => bytes.push(escaped),
+			},
+			byte => bytes.push(byte),
+		}
+	}
+
+	Some(
+		bytes
+			.split(|byte| *byte == 0)
+			.filter_map(<[u8]>::split_first)
+			.map(|(field, value)| (*field, String::from_utf8_lossy(value).into_owned()))
+			.collect(),
+	)
+}
+
+fn postgres(fields: &HashMap<u8, String>) -> Option<Classified> {
+	let code = fields.get(&b'C')?;
+	let constraint = fields.get(&b'n').cloned();
+
+	let kind = match code.as_str() {
+		"23505" => Kind::Unique {
+			constraint,
+			// the detail looks like `Key (email)=(alice@example.com) already exists.`
+			columns: fields
+				.get(&b'D')
+				.and_then(|detail| detail.strip_prefix("Key ("))
+				.and_then(|detail| detail.split_once(")="))
+				.map(|(columns, _)| {
+					columns
+						.split(", ")
+						.map(|column| column.trim_matches('"').to_string())
+						.collect()
+				})
+				.unwrap_or_default(),
+		},
+		"23503" => Kind::ForeignKey { constraint },
+		"23502" => Kind::NotNull {
+			column: fields.get(&b'c').cloned(),
+		},
+		"23514" => Kind::Check { constraint },
+		SERIALIZATION_FAILURE | retry::DEADLOCK_DETECTED => Kind::Deadlock,
+		code if code.starts_with("08") => Kind::ConnectionLost,
+		_ => Kind::Other,
+	};
+
+	Some((
+		kind,
+		Some(code.clone()),
+		fields.get(&b'M').cloned().unwrap_or_default(),
+	))
+}
+
+/// Classifies `MySQL` errors, which look like `1062 (23000): Duplicate entry 'alice@example.com' for key 'users.email'`.
+fn mysql(raw: &str) -> Option<Classified> {
+	let (number, rest) = raw.split_once(' ')?;
+	let number: u16 = number.trim_end_matches(':').parse().ok()?;
+	let (code, message) = match rest
+		.strip_prefix('(')
+		.and_then(|rest| rest.split_once("): "))
+	{
+		Some((code, message)) => (Some(code), message),
+		None => (None, rest),
+	};
+
+	let kind = match number {
+		1062 | 1586 => Kind::Unique {
+			columns: vec![],
+			constraint: quoted(message, "for key '", '\'')
+				.map(|key| key.rsplit('.').next().unwrap_or(key).to_string()),
+		},
+		1216 | 1217 | 1451 | 1452 => Kind::ForeignKey {
+			constraint: quoted(message, "CONSTRAINT `", '`').map(ToString::to_string),
+		},
+		1048 | 1364 => Kind::NotNull {
+			column: quoted(message, "Column '", '\'')
+				.or_else(|| quoted(message, "Field '", '\''))
+				.map(ToString::to_string),
+		},
+		3819 => Kind::Check {
+			constraint: quoted(message, "constraint '", '\'').map(ToString::to_string),
+		},
+		1213 => Kind::Deadlock,
+		_ if code.is_some_and(|code| code.starts_with("08")) => Kind::ConnectionLost,
+		_ => Kind::Other,
+	};
+
+	Some((kind, code.map(ToString::to_string), message.to_string()))
+}
+
+/// Classifies `SQLite` errors, which only have a message like `UNIQUE constraint failed: users.email`.
+fn sqlite(raw: &str) -> Option<Classified> {
+	// columns are reported as `table.column`
+	let column = |column: &str| {
+		column
+			.rsplit('.')
+			.next()
+			.unwrap_or(column)
+			.trim()
+			.to_string()
+	};
+
+	let (kind, sqlstate) = if let Some((_, rest)) = raw.split_once("UNIQUE constraint failed: ") {
+		// unique indexes created with an expression are reported by name instead
+		let kind = quoted(rest, "index '", '\'').map_or_else(
+			|| Kind::Unique {
+				constraint: None,
+				columns: rest.split(", ").map(column).collect(),
+			},
+			|index| Kind::Unique {
+				columns: vec![],
+				constraint: Some(index.to_string()),
+			},
+		);
+
+		(kind, "23505")
+	} else if raw.contains("FOREIGN KEY constraint failed") {
+		(Kind::ForeignKey { constraint: None }, "23503")
+	} else if let Some((_, rest)) = raw.split_once("NOT NULL constraint failed: ") {
+		(
+			Kind::NotNull {
+				column: Some(column(rest)),
+			},
+			"23502",
+		)
+	} else if let Some((_, rest)) = raw.split_once("CHECK constraint failed") {
+		let constraint = rest.trim_start_matches(':').trim();
+
+		(
+			Kind::Check {
+				constraint: (!constraint.is_empty()).then(|| constraint.to_string()),
+			},
+			"23514",
+		)
+	} else if raw.contains("database is locked") || raw.contains("database table is locked") {
+		(Kind::Deadlock, SERIALIZATION_FAILURE)
+	} else {
+		return None;
+	};
+
+	Some((kind, Some(sqlstate.to_string()), raw.to_string()))
+}
+
+/// Returns the text between the given prefix and the next `end` character.
+fn quoted<'a>(message: &'a str, prefix: &str, end: char) -> Option<&'a str> {
+	let (_, rest) = message.split_once(prefix)?;
+
+	rest.split_once(end).map(|(value, _)| value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn classify_message(message: &str) -> Error {
+		classify(
+			&rbatis::Error::from(message),
+			"insert into users (email) values (?)",
+			&[Value::String("alice@example.com".to_string())],
+		)
+	}
+
+	#[test]
+	fn classifies_postgres_errors() {
+		let error = classify_message(
+			r#"db:Notice { storage: b"SERROR\0VERROR\0C23505\0Mduplicate key value violates unique constraint \"users_email_key\"\0DKey (email)=(alice@example.com) already exists.\0spublic\0tusers\0nusers_email_key\0\0", severity: Error, message: (0, 0), code: (0, 0) }"#,
+		);
+
+		let Error::UniqueViolation {
+			constraint,
+			columns,
+			details,
+		} = error
+		else {
+			panic!("expected a unique violation, got {error:?}");
+		};
+		assert_eq!(constraint.as_deref(), Some("users_email_key"));
+		assert_eq!(columns, ["email"]);
+		assert_eq!(details.sqlstate.as_deref(), Some("23505"));
+		assert_eq!(
+			details.message,
+			r#"duplicate key value violates unique constraint "users_email_key""#
+		);
+		assert_eq!(details.sql, "insert into users (email) values (?)");
+		assert_eq!(details.bindings.len(), 1);
+
+		assert!(matches!(
+			classify_message(r#"db:Notice { storage: b"SERROR\0C40P01\0Mdeadlock detected\0" }"#),
+			Error::Deadlock { .. }
+		));
+	}
+
+	#[test]
+	fn classifies_mysql_errors() {
+		let error = classify_message(
+			"1452 (23000): Cannot add or update a child row: a foreign key constraint fails (`app`.`posts`, CONSTRAINT `posts_user_id_foreign` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`))",
+		);
+		assert!(
+			matches!(error, Error::ForeignKeyViolation { constraint: Some(ref c), .. } if c == "posts_user_id_foreign")
+		);
+
+		let error = classify_message("1048 (23000): Column 'name' cannot be null");
+		assert!(
+			matches!(error, Error::NotNullViolation { column: Some(ref c), .. } if c == "name")
+		);
+
+		assert!(matches!(
+			classify_message("1213 (40001): Deadlock found when trying to get lock"),
+			Error::Deadlock { .. }
+		));
+	}
+
+	#[test]
+	fn classifies_sqlite_errors() {
+		let error = classify_message("UNIQUE constraint failed: users.email, users.name");
+		assert!(
+			matches!(error, Error::UniqueViolation { ref columns, .. } if columns == &["email", "name"])
+		);
+
+		let error = classify_message("CHECK constraint failed: positive_balance");
+		assert!(
+			matches!(error, Error::CheckViolation { constraint: Some(ref c), .. } if c == "positive_balance")
+		);
+
+		assert!(matches!(
+			classify_message("Broken pipe (os error 32)"),
+			Error::ConnectionLost { .. }
+		));
+
+		let error = classify_message("no such table: users");
+		let Error::Query { details } = error else {
+			panic!("expected an unclassified query error, got {error:?}");
+		};
+		assert_eq!(details.message, "no such table: users");
+		assert_eq!(details.sql, "insert into users (email) values (?)");
+		assert_eq!(details.sqlstate, None);
+
+		let error = classify_message("1146 (42S02): Table 'app.users' doesn't exist");
+		assert!(matches!(
+			error.details(),
+			Some(DatabaseError { sqlstate: Some(code), .. }) if code == "42S02"
+		));
+	}
+}
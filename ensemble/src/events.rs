@@ -0,0 +1,195 @@
+use rbatis::rbdc::db::ExecResult;
+use rbs::Value;
+use std::{
+	backtrace::Backtrace,
+	future::Future,
+	sync::{Arc, RwLock},
+	time::{Duration, Instant},
+};
+
+use crate::{error, Error};
+
+type Listener = Arc<dyn Fn(&QueryEvent) + Send + Sync>;
+
+static LISTENERS: RwLock<Vec<Listener>> = RwLock::new(Vec::new());
+static SLOW_QUERY_THRESHOLD: RwLock<Option<Duration>> = RwLock::new(None);
+
+/// A query that was run against the database, passed to the listeners registered with [`listen`].
+#[derive(Debug, Clone)]
+pub struct QueryEvent {
+	/// The SQL that was run.
+	pub sql: String,
+	/// The values bound to the query.
+	pub bindings: Vec<Value>,
+	/// How long the query took to run.
+	pub duration: Duration,
+	/// The number of rows the query returned, or affected if it didn't return any.
+	pub rows: u64,
+	/// The name of the connection the query ran on.
+	pub connection: String,
+	/// The error the query failed with, if any.
+	pub error: Option<String>,
+}
+
+/// Registers a listener that is called after every query runs, whether it succeeded or not.
+///
+/// # Panics
+///
+/// Panics if another thread panicked while registering a listener.
+pub fn listen<F: Fn(&QueryEvent) + Send + Sync + 'static>(listener: F) {
+	LISTENERS.write().unwrap().push(Arc::new(listener));
+}
+
+/// Logs a warning, including where the query was made from, whenever a query takes longer than the given threshold.
+///
+/// # Panics
+///
+/// Panics if another thread panicked while setting the threshold.
+pub fn log_slow_queries(threshold: Duration) {
+	*SLOW_QUERY_THRESHOLD.write().unwrap() = Some(threshold);
+}
+
+/// The number of rows a query returned or affected.
+pub trait Rows {
+	fn rows(&self) -> u64;
+}
+
+impl Rows for Vec<Value> {
+	fn rows(&self) -> u64 {
+		self.len() as u64
+	}
+}
+
+impl Rows for ExecResult {
+	fn rows(&self) -> u64 {
+		self.rows_affected
+	}
+}
+
+/// Runs a query, timing it and reporting it to the listeners and the slow query log.
+/// Failures are classified into the matching [`Error`] variant.
+pub async fn record<T, F, Fut>(
+	connection: &str,
+	sql: &str,
+	bindings: Vec<Value>,
+	query: F,
+) -> Result<T, Error>
+where
+	T: Rows,
+	F: FnOnce(Vec<Value>) -> Fut,
+	Fut: Future<Output = Result<T, rbatis::Error>>,
+{
+	let listeners = LISTENERS.read().unwrap().clone();
+	let threshold = *SLOW_QUERY_THRESHOLD.read().unwrap();
+
+	let start = Instant::now();
+	let result = query(bindings.clone())
+		.await
+		.map_err(|e| error::classify(&e, sql, &bindings));
+	let duration = start.elapsed();
+
+	if threshold.is_some_and(|threshold| duration > threshold) {
+		tracing::warn!(
+			sql,
+			connection,
+			duration = ?duration,
+			call_site = call_site().as_deref().unwrap_or("unknown"),
+			"Slow query detected."
+		);
+	}
+
+	if !listeners.is_empty() {
+		let event = QueryEvent {
+			duration,
+			sql: sql.to_string(),
+			bindings,
+			connection: connection.to_string(),
+			rows: result.as_ref().map_or(0, Rows::rows),
+			error: result.as_ref().err().map(ToString::to_string),
+		};
+
+		for listener in listeners {
+			listener(&event);
+		}
+	}
+
+	result
+}
+
+/// Finds where the current query was made from, by walking the stack until it leaves `ensemble` and its dependencies.
+pub fn call_site() -> Option<String> {
+	first_external_frame(&Backtrace::force_capture().to_string()).map(ToString::to_string)
+}
+
+fn first_external_frame(backtrace: &str) -> Option<&str> {
+	const INTERNAL: [&str; 12] = [
+		"std::",
+		"core::",
+		"alloc::",
+		"tokio::",
+		"futures",
+		"ensemble::",
+		"rbatis::",
+		"rbdc",
+		"mobc::",
+		"test::",
+		"__rust",
+		"rust_begin_unwind",
+	];
+
+	let mut lines = backtrace.lines().map(str::trim);
+
+	while let Some(line) = lines.next() {
+		let Some((index, symbol)) = line.split_once(": ") else {
+			continue;
+		};
+
+		if !index.bytes().all(|b| b.is_ascii_digit()) {
+			continue;
+		}
+
+		// methods generated by `#[derive(Model)]` live in the user's crate, but aren't where the query was made from.
+		let symbol = symbol.trim_start_matches('<');
+		if symbol.contains(" as ensemble::")
+			|| symbol.contains("impl ensemble::")
+			|| INTERNAL.iter().any(|internal| symbol.starts_with(internal))
+		{
+			continue;
+		}
+
+		return Some(
+			lines
+				.next()
+				.and_then(|line| line.strip_prefix("at "))
+				.unwrap_or(symbol),
+		);
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn finds_the_first_frame_outside_ensemble() {
+		let backtrace = "   0: std::backtrace::Backtrace::force_capture
+             at /rustc/library/std/src/backtrace.rs:312:9
+   1: ensemble::events::record::{{closure}}
+             at ./ensemble/src/events.rs:90:4
+   2: <app::User as ensemble::Model>::find::{{closure}}
+             at ./src/models.rs:3:10
+   3: app::models::_::<impl ensemble::Model for app::models::Post>::create::{{closure}}
+             at ./src/models.rs:12:10
+   4: app::handlers::show::{{closure}}
+             at ./src/handlers.rs:12:5
+   5: tokio::runtime::task::core::Core<T,S>::poll";
+
+		assert_eq!(
+			first_external_frame(backtrace),
+			Some("./src/handlers.rs:12:5")
+		);
+		assert_eq!(first_external_frame("   0: std::rt::lang_start"), None);
+	}
+}
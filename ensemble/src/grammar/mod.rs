@@ -0,0 +1,452 @@
+use itertools::Itertools;
+use std::fmt::Write;
+
+pub use self::{mysql::MySQL, postgres::Postgres, sqlite::SQLite};
+use crate::{
+	migrations::schema::{Column, ForeignIndex, Type},
+	query::Builder,
+};
+
+mod mysql;
+mod postgres;
+mod sqlite;
+
+/// Compiles queries and schema definitions into SQL for a specific database.
+///
+/// Every method but [`Grammar::uuid_default`] and [`Grammar::compile_migrations_table`] has a default implementation that targets standard SQL,
+/// so a dialect only needs to override the parts that differ.
+///
+/// The query compilers are built from the public `compile_*` methods of [`Builder`] (like [`Builder::compile_columns`] and [`Builder::compile_clauses`]),
+/// which dialects defined in other crates can use to override them.
+///
+/// ## Example
+///
+/// ```rust
+/// use ensemble::{grammar::Grammar, query::Builder};
+///
+/// struct Dialect;
+///
+/// impl Grammar for Dialect {
+///     fn uuid_default(&self) -> &'static str {
+///         "gen_random_uuid()"
+///     }
+///
+///     fn compile_migrations_table(&self) -> String {
+///         "CREATE TABLE IF NOT EXISTS migrations (id BIGINT PRIMARY KEY, migration TEXT, batch BIGINT)".to_string()
+///     }
+///
+///     // this dialect has no LIMIT clause, so it deletes every matching row
+///     fn compile_delete(&self, query: &Builder) -> String {
+///         format!("DELETE FROM {}{}", query.table(), query.compile_conditions(self))
+///     }
+/// }
+/// ```
+pub trait Grammar: Send + Sync {
+	/// Wrap a single identifier (like a table or column name) in quotes.
+	fn wrap_identifier(&self, identifier: &str) -> String {
+		format!("\"{identifier}\"")
+	}
+
+	/// Wrap a column name, which may be qualified with its table (like `users.id`) and aliased (like `users.id as user_id`).
+	fn wrap(&self, column: &str) -> String {
+		if let Some((column, alias)) = split_alias(column) {
+			return format!("{} AS {}", self.wrap(column), self.wrap_identifier(alias));
+		}
+
+		column
+			.split('.')
+			.map(|part| {
+				if part == "*" {
+					part.to_string()
+				} else {
+					self.wrap_identifier(part)
+				}
+			})
+			.join(".")
+	}
+
+	/// Compile a select query.
+	fn compile_select(&self, query: &Builder) -> String {
+		let select = format!(
+			"SELECT {} FROM {}{}",
+			query.compile_columns(self),
+			query.compile_from(self),
+			query.compile_conditions(self)
+		);
+
+		format!(
+			"{}{}{}",
+			query.compile_ctes(self),
+			query.compile_unions(self, select),
+			query.compile_ordering(self)
+		)
+	}
+
+	/// Wrap one of the selects combined by a union.
+	fn wrap_union(&self, select: &str) -> String {
+		format!("({select})")
+	}
+
+	/// Compile a query that counts the matching records.
+	fn compile_count(&self, query: &Builder) -> String {
+		// the combined results of a union, the groups of a grouped query and distinct rows can only be counted from a subquery
+		if query.counts_subquery() {
+			return format!(
+				"SELECT COUNT(*) FROM ({}) AS {}",
+				self.compile_select(query),
+				self.wrap_identifier("temp_table")
+			);
+		}
+
+		format!(
+			"{}SELECT COUNT(*) FROM {}{}",
+			query.compile_ctes(self),
+			query.compile_from(self),
+			query.compile_clauses(self)
+		)
+	}
+
+	/// Compile a query that calculates an aggregate (like `SUM` or `MAX`) of the given column over the matching records.
+	fn compile_aggregate(&self, query: &Builder, function: &str, column: &str) -> String {
+		if query.has_unions() {
+			return format!(
+				"SELECT {function}({column}) AS aggregate FROM ({}) AS {}",
+				self.compile_select(query),
+				self.wrap_identifier("temp_table")
+			);
+		}
+
+		format!(
+			"{}SELECT {function}({column}) AS aggregate FROM {}{}",
+			query.compile_ctes(self),
+			query.compile_from(self),
+			query.compile_clauses(self)
+		)
+	}
+
+	/// Compile an insert statement for the given columns.
+	fn compile_insert(&self, table: &str, columns: &[String]) -> String {
+		format!(
+			"INSERT INTO {table} ({}) VALUES ({})",
+			columns.iter().map(|column| self.wrap(column)).join(", "),
+			columns.iter().map(|_| "?").join(", ")
+		)
+	}
+
+	/// Compile an update statement for the given columns.
+	fn compile_update(&self, query: &Builder, columns: &[String]) -> String {
+		format!(
+			"UPDATE {} SET {}{}",
+			query.table(),
+			columns
+				.iter()
+				.map(|column| format!("{} = ?", self.wrap(column)))
+				.join(", "),
+			query.compile_clauses(self)
+		)
+	}
+
+	/// Compile an update statement that increments the given column.
+	fn compile_increment(&self, query: &Builder, column: &str) -> String {
+		let column = self.wrap(column);
+
+		format!(
+			"UPDATE {} SET {column} = {column} + ?{}",
+			query.table(),
+			query.compile_clauses(self)
+		)
+	}
+
+	/// Compile a delete statement.
+	fn compile_delete(&self, query: &Builder) -> String {
+		format!(
+			"DELETE FROM {}{}",
+			query.table(),
+			query.compile_clauses(self)
+		)
+	}
+
+	/// Compile a truncate statement.
+	fn compile_truncate(&self, table: &str) -> String {
+		format!("TRUNCATE TABLE {table}")
+	}
+
+	/// Compile a column type.
+	fn compile_type(&self, r#type: &Type) -> String {
+		match r#type {
+			Type::Json => "json".to_string(),
+			Type::Uuid => "uuid".to_string(),
+			Type::Text => "text".to_string(),
+			Type::Boolean => "boolean".to_string(),
+			Type::BigInteger => "bigint".to_string(),
+			Type::Timestamp => "timestamp".to_string(),
+			Type::String(size) => format!("varchar({size})"),
+			Type::Enum(name, values) => format!(
+				"varchar(255) check({name} in ({}))",
+				values
+					.iter()
+					.map(|v| format!("'{}'", v.replace('\'', "\\'")))
+					.join(", ")
+			),
+		}
+	}
+
+	/// Compile the type of the given column, including any type-level modifiers.
+	fn compile_column_type(&self, column: &Column) -> String {
+		self.compile_type(&column.r#type)
+	}
+
+	/// The expression used as the default value of columns that automatically generate UUIDs.
+	fn uuid_default(&self) -> &'static str;
+
+	/// The expression used as the default value of columns that use the current timestamp.
+	fn current_timestamp(&self) -> &'static str {
+		"CURRENT_TIMESTAMP"
+	}
+
+	/// The keyword that makes the given column auto-increment, if the dialect needs one.
+	fn compile_auto_increment(&self, _column: &Column) -> Option<&'static str> {
+		None
+	}
+
+	/// Compile any dialect-specific modifiers that go at the end of a column definition.
+	fn compile_column_modifiers(&self, _column: &Column) -> String {
+		String::new()
+	}
+
+	/// Compile a column definition.
+	///
+	/// # Panics
+	///
+	/// Panics if the column has both a default value and automatically generates UUIDs.
+	fn compile_column(&self, column: &Column) -> String {
+		let mut sql = format!("{} {}", column.name, self.compile_column_type(column));
+
+		if column.nullable {
+			sql.push_str(" NULL");
+		} else {
+			sql.push_str(" NOT NULL");
+		}
+
+		if let Some(comment) = &column.comment {
+			let _ = write!(sql, " COMMENT {comment}");
+		}
+
+		if let Some(collation) = &column.collation {
+			let _ = write!(sql, " COLLATE {collation}");
+		}
+
+		if let Some(default) = &column.default {
+			if column.r#type == Type::Json {
+				let _ = write!(sql, " DEFAULT '{}'", default.as_str().unwrap());
+			} else {
+				let _ = write!(sql, " DEFAULT {default}");
+			}
+		}
+
+		if column.uuid {
+			assert!(
+				column.default.is_none(),
+				"cannot set a default valud and automatically generate UUIDs at the same time"
+			);
+
+			let _ = write!(sql, " DEFAULT {}", self.uuid_default());
+		}
+
+		if let Some(index) = &column.index {
+			let _ = write!(sql, " INDEX {index}");
+		}
+
+		if column.primary {
+			sql.push_str(" PRIMARY KEY");
+		}
+
+		if column.auto_increment {
+			if let Some(keyword) = self.compile_auto_increment(column) {
+				let _ = write!(sql, " {keyword}");
+			}
+		}
+
+		if column.unique {
+			sql.push_str(" UNIQUE");
+		}
+
+		if column.use_current {
+			let _ = write!(sql, " DEFAULT {}", self.current_timestamp());
+		}
+
+		sql.push_str(&self.compile_column_modifiers(column));
+
+		sql
+	}
+
+	/// Compile a foreign key constraint. Returns the SQL that goes inside the `CREATE TABLE` statement,
+	/// and optionally a statement that should be run after the table has been created.
+	///
+	/// # Panics
+	///
+	/// Panics if the foreign column hasn't been specified.
+	fn compile_foreign(&self, index: &ForeignIndex) -> (String, Option<String>) {
+		let mut sql = format!(
+			"FOREIGN KEY ({}) REFERENCES {}({})",
+			index.column,
+			index.table,
+			index.foreign_column()
+		);
+
+		sql.push_str(&index.compile_actions());
+
+		(
+			sql,
+			Some(format!(
+				"CREATE INDEX {} ON {}({});",
+				index.index_name(),
+				index.origin_table,
+				index.column
+			)),
+		)
+	}
+
+	/// Compile the options that go after the column list of a `CREATE TABLE` statement.
+	fn compile_table_options(&self) -> String {
+		String::new()
+	}
+
+	/// Compile a `CREATE TABLE` statement.
+	fn compile_create(&self, table: &str, columns: &[Column], foreign: &[ForeignIndex]) -> String {
+		let (inline_sql, post_sql): (Vec<_>, Vec<_>) = foreign
+			.iter()
+			.map(|index| self.compile_foreign(index))
+			.unzip();
+
+		format!(
+			"CREATE TABLE {table} ({}) {}; {}",
+			columns
+				.iter()
+				.map(|column| self.compile_column(column))
+				.chain(inline_sql)
+				.join(", "),
+			self.compile_table_options(),
+			post_sql.into_iter().flatten().join("\n")
+		)
+	}
+
+	/// Compile a `DROP TABLE` statement.
+	fn compile_drop(&self, table: &str) -> String {
+		// table names can't be bound as parameters, so we have to inline it into the query.
+		format!("DROP TABLE {table}")
+	}
+
+	/// Compile the statement that creates the table used to keep track of migrations, if it doesn't exist.
+	fn compile_migrations_table(&self) -> String;
+
+	/// Compile a statement that creates a savepoint inside the current transaction.
+	fn compile_savepoint(&self, name: &str) -> String {
+		format!("SAVEPOINT {name}")
+	}
+
+	/// Compile a statement that releases (commits) a savepoint.
+	fn compile_release_savepoint(&self, name: &str) -> String {
+		format!("RELEASE SAVEPOINT {name}")
+	}
+
+	/// Compile a statement that rolls back to a savepoint.
+	fn compile_rollback_to_savepoint(&self, name: &str) -> String {
+		format!("ROLLBACK TO SAVEPOINT {name}")
+	}
+}
+
+/// Splits a column like `email as contact` into the column and its alias.
+fn split_alias(column: &str) -> Option<(&str, &str)> {
+	let index = column.to_ascii_lowercase().find(" as ")?;
+
+	Some((column[..index].trim(), column[index + 4..].trim()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::migrations::Schema;
+
+	#[test]
+	fn wraps_identifiers_for_each_dialect() {
+		assert_eq!(MySQL.wrap("users.id"), "`users`.`id`");
+		assert_eq!(Postgres.wrap("users.id"), "\"users\".\"id\"");
+		assert_eq!(SQLite.wrap("users.id"), "\"users\".\"id\"");
+		assert_eq!(MySQL.wrap("users.*"), "`users`.*");
+		assert_eq!(
+			Postgres.wrap("users.email as contact"),
+			"\"users\".\"email\" AS \"contact\""
+		);
+	}
+
+	#[test]
+	fn wraps_compared_columns_named_after_reserved_words() {
+		let query = Builder::new("orders".to_string()).where_column("order", "=", "users.user");
+
+		assert_eq!(
+			MySQL.compile_select(&query),
+			"SELECT * FROM orders WHERE `order` = `users`.`user`"
+		);
+		assert_eq!(
+			Postgres.compile_select(&query),
+			"SELECT * FROM orders WHERE \"order\" = \"users\".\"user\""
+		);
+	}
+
+	#[test]
+	fn compiles_inserts_for_each_dialect() {
+		let columns = ["name".to_string(), "email".to_string()];
+
+		assert_eq!(
+			MySQL.compile_insert("users", &columns),
+			"INSERT INTO users (`name`, `email`) VALUES (?, ?)"
+		);
+		assert_eq!(
+			Postgres.compile_insert("users", &columns),
+			"INSERT INTO users (\"name\", \"email\") VALUES (?, ?)"
+		);
+	}
+
+	#[test]
+	fn compiles_truncates_for_each_dialect() {
+		assert_eq!(MySQL.compile_truncate("users"), "TRUNCATE TABLE users");
+		assert_eq!(Postgres.compile_truncate("users"), "TRUNCATE TABLE users");
+		assert_eq!(SQLite.compile_truncate("users"), "DELETE FROM users");
+	}
+
+	#[test]
+	fn compiles_drops_with_the_table_name_inlined() {
+		assert_eq!(MySQL.compile_drop("users"), "DROP TABLE users");
+		assert_eq!(Postgres.compile_drop("users"), "DROP TABLE users");
+		assert_eq!(SQLite.compile_drop("users"), "DROP TABLE users");
+	}
+
+	#[test]
+	fn compiles_create_table_for_each_dialect() {
+		let schema = |table: &mut crate::migrations::schema::Table| {
+			table.id();
+			table.string("email").unique(true);
+			table.foreign_id("user_id").on_delete("cascade");
+			table.timestamps();
+		};
+
+		assert_eq!(
+			Schema::to_sql(&MySQL, "posts", schema).unwrap(),
+			"CREATE TABLE posts (id bigint unsigned NOT NULL PRIMARY KEY AUTO_INCREMENT, \
+			 email varchar(255) NOT NULL UNIQUE, user_id bigint unsigned NOT NULL, \
+			 created_at timestamp NULL DEFAULT CURRENT_TIMESTAMP, \
+			 updated_at timestamp NULL ON UPDATE CURRENT_TIMESTAMP, \
+			 KEY posts_user_id_foreign (user_id), CONSTRAINT posts_user_id_foreign FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE) \
+			 ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci; "
+		);
+		assert_eq!(
+			Schema::to_sql(&Postgres, "posts", schema).unwrap(),
+			"CREATE TABLE posts (id bigserial NOT NULL PRIMARY KEY, \
+			 email varchar(255) NOT NULL UNIQUE, user_id bigint NOT NULL, \
+			 created_at timestamp NULL DEFAULT now(), updated_at timestamp NULL, \
+			 FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE) ; \
+			 CREATE INDEX posts_user_id_foreign ON posts(user_id);"
+		);
+	}
+}
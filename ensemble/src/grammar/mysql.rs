@@ -0,0 +1,94 @@
+use itertools::Itertools;
+use std::fmt::Write;
+
+use super::Grammar;
+use crate::migrations::schema::{Column, ForeignIndex, Type};
+
+/// The grammar for `MySQL` databases.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MySQL;
+
+impl Grammar for MySQL {
+	fn wrap_identifier(&self, identifier: &str) -> String {
+		format!("`{identifier}`")
+	}
+
+	fn compile_type(&self, r#type: &Type) -> String {
+		match r#type {
+			Type::Enum(_, values) => format!(
+				"enum({})",
+				values
+					.iter()
+					.map(|v| format!("'{}'", v.replace('\'', "\\'")))
+					.join(", ")
+			),
+			Type::Json => "json".to_string(),
+			Type::Uuid => "uuid".to_string(),
+			Type::Text => "text".to_string(),
+			Type::Boolean => "boolean".to_string(),
+			Type::BigInteger => "bigint".to_string(),
+			Type::Timestamp => "timestamp".to_string(),
+			Type::String(size) => format!("varchar({size})"),
+		}
+	}
+
+	fn compile_column_type(&self, column: &Column) -> String {
+		let db_type = self.compile_type(&column.r#type);
+
+		if column.unsigned {
+			format!("{db_type} unsigned")
+		} else {
+			db_type
+		}
+	}
+
+	fn uuid_default(&self) -> &'static str {
+		"(UUID())"
+	}
+
+	fn compile_auto_increment(&self, _column: &Column) -> Option<&'static str> {
+		Some("AUTO_INCREMENT")
+	}
+
+	fn compile_column_modifiers(&self, column: &Column) -> String {
+		let mut sql = String::new();
+
+		if column.use_current_on_update {
+			sql.push_str(" ON UPDATE CURRENT_TIMESTAMP");
+		}
+
+		if let Some(after) = &column.after {
+			let _ = write!(sql, " AFTER {after}");
+		}
+
+		sql
+	}
+
+	fn compile_foreign(&self, index: &ForeignIndex) -> (String, Option<String>) {
+		let index_name = index.index_name();
+		let mut sql = format!(
+			"KEY {index_name} ({}), CONSTRAINT {index_name} FOREIGN KEY ({}) REFERENCES {}({})",
+			index.column,
+			index.column,
+			index.table,
+			index.foreign_column()
+		);
+
+		sql.push_str(&index.compile_actions());
+
+		(sql, None)
+	}
+
+	fn compile_table_options(&self) -> String {
+		"ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci".to_string()
+	}
+
+	fn compile_migrations_table(&self) -> String {
+		"create table if not exists migrations (
+                id int unsigned not null auto_increment primary key,
+                migration varchar(255) not null unique,
+                batch int not null
+            )"
+		.to_string()
+	}
+}
@@ -0,0 +1,33 @@
+use super::Grammar;
+use crate::migrations::schema::{Column, Type};
+
+/// The grammar for `PostgreSQL` databases.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Postgres;
+
+impl Grammar for Postgres {
+	fn compile_column_type(&self, column: &Column) -> String {
+		if column.r#type == Type::BigInteger && column.auto_increment {
+			return "bigserial".to_string();
+		}
+
+		self.compile_type(&column.r#type)
+	}
+
+	fn uuid_default(&self) -> &'static str {
+		"(gen_random_uuid())"
+	}
+
+	fn current_timestamp(&self) -> &'static str {
+		"now()"
+	}
+
+	fn compile_migrations_table(&self) -> String {
+		"create table if not exists migrations (
+                id serial primary key,
+                migration varchar(255) not null unique,
+                batch int not null
+            )"
+		.to_string()
+	}
+}
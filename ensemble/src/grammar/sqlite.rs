@@ -0,0 +1,57 @@
+use itertools::Itertools;
+
+use super::Grammar;
+use crate::migrations::schema::{Column, Type};
+
+/// The grammar for `SQLite` databases.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SQLite;
+
+impl Grammar for SQLite {
+	fn compile_truncate(&self, table: &str) -> String {
+		// SQLite doesn't have a TRUNCATE statement, but optimizes unqualified deletes into one.
+		format!("DELETE FROM {table}")
+	}
+
+	fn wrap_union(&self, select: &str) -> String {
+		// SQLite doesn't allow parentheses around the selects in a union.
+		format!("SELECT * FROM ({select})")
+	}
+
+	fn compile_type(&self, r#type: &Type) -> String {
+		// SQLite picks a column's affinity from its declared type, so we stick to names that map to TEXT and INTEGER.
+		match r#type {
+			Type::Json | Type::Text => "text".to_string(),
+			Type::Uuid => "varchar(36)".to_string(),
+			Type::BigInteger => "integer".to_string(),
+			Type::Boolean => "boolean".to_string(),
+			Type::Timestamp => "timestamp".to_string(),
+			Type::String(size) => format!("varchar({size})"),
+			Type::Enum(name, values) => format!(
+				"varchar(255) check({name} in ({}))",
+				values
+					.iter()
+					.map(|v| format!("'{}'", v.replace('\'', "''")))
+					.join(", ")
+			),
+		}
+	}
+
+	fn uuid_default(&self) -> &'static str {
+		"(lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2) || '-' || substr('89ab', abs(random()) % 4 + 1, 1) || substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))))"
+	}
+
+	fn compile_auto_increment(&self, column: &Column) -> Option<&'static str> {
+		// SQLite only accepts AUTOINCREMENT as part of the primary key constraint.
+		column.primary.then_some("AUTOINCREMENT")
+	}
+
+	fn compile_migrations_table(&self) -> String {
+		"create table if not exists migrations (
+                id integer primary key autoincrement,
+                migration varchar(255) not null unique,
+                batch int not null
+            )"
+		.to_string()
+	}
+}
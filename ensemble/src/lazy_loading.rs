@@ -0,0 +1,89 @@
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	future::Future,
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{events, Error};
+
+static PREVENT_LAZY_LOADING: AtomicBool = AtomicBool::new(false);
+static PANIC_ON_LAZY_LOADING: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+	static DETECTOR: Detector;
+}
+
+struct Detector {
+	threshold: usize,
+	/// How many times each relationship has been lazy loaded, keyed by model and relationship name.
+	loads: RefCell<HashMap<(&'static str, &'static str), usize>>,
+}
+
+/// Disallows loading relationships that weren't eager loaded with [`Model::with`](crate::Model::with) or [`Model::load`](crate::Model::load).
+///
+/// Once enabled, calling [`Relationship::get`](crate::relationships::Relationship::get) on a relationship that wasn't loaded panics in debug builds,
+/// and returns an [`Error::LazyLoadingViolation`] instead of querying the database in release builds. Use [`prevent_lazy_loading_with`] to pick the behavior yourself.
+pub fn prevent_lazy_loading(prevent: bool) {
+	PREVENT_LAZY_LOADING.store(prevent, Ordering::Relaxed);
+	PANIC_ON_LAZY_LOADING.store(cfg!(debug_assertions), Ordering::Relaxed);
+}
+
+/// Disallows lazy loading relationships like [`prevent_lazy_loading`], panicking if `panic` is `true` and returning an error otherwise, in every build.
+pub fn prevent_lazy_loading_with(panic: bool) {
+	PREVENT_LAZY_LOADING.store(true, Ordering::Relaxed);
+	PANIC_ON_LAZY_LOADING.store(panic, Ordering::Relaxed);
+}
+
+/// Runs the given future (like a request handler), logging a warning whenever the same relationship is lazy loaded `threshold` times within it.
+///
+/// Lazy loading a relationship for every model in a list runs one query per model (the "N+1" problem), which is usually better handled by eager loading it.
+/// The warning names the model and relationship, and where the last load was made from.
+pub async fn detect_n_plus_one<F: Future>(threshold: usize, future: F) -> F::Output {
+	DETECTOR
+		.scope(
+			Detector {
+				threshold,
+				loads: RefCell::default(),
+			},
+			future,
+		)
+		.await
+}
+
+/// Called before a relationship is lazy loaded.
+///
+/// # Errors
+///
+/// Returns an error if lazy loading has been disabled with [`prevent_lazy_loading`].
+///
+/// # Panics
+///
+/// Panics instead of returning an error in debug builds, or if enabled with [`prevent_lazy_loading_with`].
+pub fn check(model: &'static str, relation: &'static str) -> Result<(), Error> {
+	if PREVENT_LAZY_LOADING.load(Ordering::Relaxed) {
+		let error = Error::LazyLoadingViolation { model, relation };
+
+		assert!(!PANIC_ON_LAZY_LOADING.load(Ordering::Relaxed), "{error}");
+
+		return Err(error);
+	}
+
+	let _ = DETECTOR.try_with(|detector| {
+		let mut loads = detector.loads.borrow_mut();
+		let count = loads.entry((model, relation)).or_default();
+		*count += 1;
+
+		if *count == detector.threshold {
+			tracing::warn!(
+				model,
+				relation,
+				count = *count,
+				call_site = events::call_site().as_deref().unwrap_or("unknown"),
+				"Possible N+1 query detected, consider eager loading the relationship."
+			);
+		}
+	});
+
+	Ok(())
+}
@@ -25,14 +25,33 @@ use std::{
 };
 
 mod connection;
+mod error;
+mod events;
+pub mod grammar;
+mod lazy_loading;
 pub mod migrations;
+pub mod pagination;
 pub mod query;
 pub mod relationships;
+pub mod retry;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transaction;
 pub mod types;
 pub mod value;
-#[cfg(any(feature = "mysql", feature = "postgres"))]
-pub use connection::setup;
+pub use connection::{pool_stats, sticky, PoolStats};
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub use connection::{
+	setup, setup_named, setup_named_with, setup_named_with_replicas, setup_with,
+	setup_with_replicas, SetupError, SetupOptions, TlsMode,
+};
+#[cfg(feature = "testing")]
+pub use ensemble_derive::test;
 pub use ensemble_derive::Model;
+pub use error::DatabaseError;
+pub use events::{listen, log_slow_queries, QueryEvent};
+pub use lazy_loading::{detect_n_plus_one, prevent_lazy_loading, prevent_lazy_loading_with};
+pub use transaction::{retry_transaction, retry_transaction_on, transaction, transaction_on};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -43,9 +62,6 @@ pub enum Error {
 	#[error(transparent)]
 	Validation(#[from] validator::ValidationErrors),
 
-	#[error("{0}")]
-	Database(String),
-
 	#[error("The {0} field is required.")]
 	Required(&'static str),
 
@@ -55,11 +71,85 @@ pub enum Error {
 	#[error("The model could not be found.")]
 	NotFound,
 
-	#[error("The unique constraint was violated.")]
-	UniqueViolation,
+	/// The database didn't report the primary key of a newly created model.
+	#[error("The database didn't return the primary key of the created [{model}].")]
+	MissingPrimaryKey {
+		/// The name of the model that was created.
+		model: &'static str,
+	},
+
+	#[error("The unique constraint was violated: {details}")]
+	UniqueViolation {
+		/// The name of the violated constraint, if the database reported it.
+		constraint: Option<String>,
+		/// The columns covered by the constraint, if the database reported them.
+		columns: Vec<String>,
+		details: Box<DatabaseError>,
+	},
+
+	#[error("The foreign key constraint was violated: {details}")]
+	ForeignKeyViolation {
+		/// The name of the violated constraint, if the database reported it.
+		constraint: Option<String>,
+		details: Box<DatabaseError>,
+	},
+
+	#[error("A required column was null: {details}")]
+	NotNullViolation {
+		/// The column that was null, if the database reported it.
+		column: Option<String>,
+		details: Box<DatabaseError>,
+	},
+
+	#[error("The check constraint was violated: {details}")]
+	CheckViolation {
+		/// The name of the violated constraint, if the database reported it.
+		constraint: Option<String>,
+		details: Box<DatabaseError>,
+	},
+
+	#[error("The transaction deadlocked or could not be serialized: {details}")]
+	Deadlock { details: Box<DatabaseError> },
+
+	#[error("The connection to the database was lost: {details}")]
+	ConnectionLost { details: Box<DatabaseError> },
+
+	/// A query failed with an error that doesn't match any of the other variants.
+	#[error("The query failed: {details}")]
+	Query { details: Box<DatabaseError> },
 
 	#[error("The query is invalid.")]
 	InvalidQuery,
+
+	#[error("The pagination cursor is invalid.")]
+	InvalidCursor,
+
+	#[error(
+		"Attempted to lazy load [{relation}] on model [{model}], but lazy loading is disabled."
+	)]
+	LazyLoadingViolation {
+		/// The name of the model the relationship is defined on.
+		model: &'static str,
+		/// The name of the relationship.
+		relation: &'static str,
+	},
+}
+
+impl Error {
+	/// What the database reported about the failed query, if the error was classified from a database error.
+	#[must_use]
+	pub fn details(&self) -> Option<&DatabaseError> {
+		match self {
+			Self::UniqueViolation { details, .. }
+			| Self::ForeignKeyViolation { details, .. }
+			| Self::NotNullViolation { details, .. }
+			| Self::CheckViolation { details, .. }
+			| Self::Deadlock { details }
+			| Self::ConnectionLost { details }
+			| Self::Query { details } => Some(details),
+			_ => None,
+		}
+	}
 }
 
 pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + Default {
@@ -82,6 +172,9 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 	/// The name of the primary key field for the model.
 	const PRIMARY_KEY: &'static str;
 
+	/// The name of the database connection the model uses.
+	const CONNECTION: &'static str = connection::DEFAULT;
+
 	/// Returns the value of the model's primary key.
 	fn primary_key(&self) -> &Self::PrimaryKey;
 
@@ -134,7 +227,7 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 				.await?;
 
 			if rows_affected != 1 {
-				return Err(Error::UniqueViolation);
+				return Err(Error::NotFound);
 			}
 
 			Ok(())
@@ -151,6 +244,8 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 	#[must_use]
 	fn query() -> Builder {
 		Builder::new(Self::TABLE_NAME.to_string())
+			.on(Self::CONNECTION)
+			.relations(Self::existence_query)
 	}
 
 	/// Begin querying a model with eager loading.
@@ -165,7 +260,7 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 	) -> impl Future<Output = Result<(), Error>> + Send {
 		async move {
 			for relation in relation.into().list() {
-				let rows = self.eager_load(&relation, &[&self]).get_rows().await?;
+				let rows = self.eager_load(&relation, &[self]).get_rows().await?;
 
 				self.fill_relation(&relation, &rows)?;
 			}
@@ -190,7 +285,7 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 				.await?;
 
 			if rows_affected != 1 {
-				return Err(Error::UniqueViolation);
+				return Err(Error::NotFound);
 			}
 
 			Ok(())
@@ -212,6 +307,11 @@ pub trait Model: DeserializeOwned + Serialize + Sized + Send + Sync + Debug + De
 	#[doc(hidden)]
 	fn eager_load(&self, relation: &str, related: &[&Self]) -> Builder;
 
+	/// Get the query for checking a relationship from a query on the model, used by [`Builder::where_has`].
+	/// This method is used internally by Ensemble, and should not be called directly.
+	#[doc(hidden)]
+	fn existence_query(relation: &str) -> Builder;
+
 	/// Fill a relationship for a set of models.
 	/// This method is used internally by Ensemble, and should not be called directly.
 	#[doc(hidden)]
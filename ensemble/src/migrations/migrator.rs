@@ -1,33 +1,54 @@
 use std::collections::HashMap;
 
 use rbs::{from_value, Value};
-use tokio::sync::Mutex;
 
 use super::{Error, Migration};
 use crate::{
 	connection::{self, Connection},
-	value,
+	events, transaction_on, value,
 };
 
-pub static MIGRATE_CONN: Mutex<Option<Connection>> = Mutex::const_new(None);
+tokio::task_local! {
+	/// The name of the connection the migration currently running in this task targets.
+	static MIGRATING: String;
+}
+
+/// Returns the name of the connection the running migration targets.
+///
+/// # Errors
+///
+/// Returns an error if no migration is running in the current task.
+pub(super) fn current_connection() -> Result<String, Error> {
+	MIGRATING.try_with(Clone::clone).map_err(|_| Error::Lock)
+}
 
 /// The migration runner.
 pub struct Migrator {
 	batch: u64,
-	connection: Connection,
+	connection: String,
 	state: Vec<StoredMigration>,
 	migrations: Vec<(String, Box<dyn Migration>)>,
 }
 
 impl Migrator {
-	/// Creates a new [`Migrator`].
+	/// Creates a new [`Migrator`] for the default connection.
 	///
 	/// # Errors
 	///
 	/// Returns an error if a connection to the database cannot be established, or if the migrations cannot be retrieved.
 	pub async fn new() -> Result<Self, Error> {
-		let mut conn = connection::get().await?;
-		let state = Self::get_state(&mut conn).await?;
+		Self::on(connection::DEFAULT).await
+	}
+
+	/// Creates a new [`Migrator`] for the named connection.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a connection to the database cannot be established, or if the migrations cannot be retrieved.
+	pub async fn on(connection: &str) -> Result<Self, Error> {
+		let mut conn = connection::get(connection).await?;
+		let state = Self::get_state(&mut conn, connection).await?;
+		drop(conn);
 		let batch = state
 			.iter()
 			.map(|m| m.batch)
@@ -44,7 +65,7 @@ impl Migrator {
 		Ok(Self {
 			state,
 			batch,
-			connection: conn,
+			connection: connection.to_string(),
 			migrations: Vec::new(),
 		})
 	}
@@ -95,52 +116,35 @@ impl Migrator {
 
 			tracing::trace!("Running migration [{name}].");
 
-			self.connection
-				.exec("begin", vec![])
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
+			let result = transaction_on(&self.connection, || async {
+				MIGRATING
+					.scope(self.connection.clone(), migration.up())
+					.await?;
 
-			MIGRATE_CONN
-				.try_lock()
-				.map_err(|_| Error::Lock)?
-				.replace(self.connection);
-
-			let migration_result = migration.up().await;
-
-			self.connection = MIGRATE_CONN
-				.try_lock()
-				.map_err(|_| Error::Lock)?
-				.take()
-				.ok_or(Error::Lock)?;
+				let sql = "insert into migrations (migration, batch) values (?, ?)";
+				let mut conn = connection::get(&self.connection).await?;
+				events::record(
+					&self.connection,
+					sql,
+					vec![value::for_db(name)?, value::for_db(self.batch)?],
+					|bindings| conn.exec(sql, bindings),
+				)
+				.await?;
 
-			if let Err(e) = migration_result {
-				self.connection
-					.exec("rollback", vec![])
-					.await
-					.map_err(|e| Error::Database(e.to_string()))?;
+				Ok::<_, Error>(())
+			})
+			.await;
 
+			if let Err(e) = result {
 				tracing::debug!("Rolled back changes for migration [{name}].");
 
 				return Err(e);
 			}
 
-			self.connection
-				.exec(
-					"insert into migrations (migration, batch) values (?, ?)",
-					vec![value::for_db(name)?, value::for_db(self.batch)?],
-				)
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
-
-			self.connection
-				.exec("commit", vec![])
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
-
 			self.state.push(StoredMigration {
 				id: 0,
 				batch: self.batch,
-				migration: name.to_string(),
+				migration: name.clone(),
 			});
 
 			tracing::info!("Successfully ran migration [{name}].");
@@ -154,7 +158,7 @@ impl Migrator {
 	/// # Errors
 	///
 	/// Returns an error if the migrations fail, or if a connection to the database cannot be established.
-	pub async fn rollback(mut self, batches: u64) -> Result<(), Error> {
+	pub async fn rollback(self, batches: u64) -> Result<(), Error> {
 		let migrations = self
 			.state
 			.into_iter()
@@ -168,70 +172,61 @@ impl Migrator {
 				.find(|(name, _)| name == &record.migration)
 				.ok_or_else(|| Error::NotFound(record.migration.clone()))?;
 
-			self.connection
-				.exec("begin", vec![])
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
+			let result = transaction_on(&self.connection, || async {
+				MIGRATING
+					.scope(self.connection.clone(), migration.down())
+					.await?;
 
-			MIGRATE_CONN
-				.try_lock()
-				.map_err(|_| Error::Lock)?
-				.replace(self.connection);
-
-			let migration_result = migration.down().await;
-
-			self.connection = MIGRATE_CONN
-				.try_lock()
-				.map_err(|_| Error::Lock)?
-				.take()
-				.ok_or(Error::Lock)?;
+				let sql = "delete from migrations where id = ?";
+				let mut conn = connection::get(&self.connection).await?;
+				events::record(
+					&self.connection,
+					sql,
+					vec![Value::U64(record.id)],
+					|bindings| conn.exec(sql, bindings),
+				)
+				.await?;
 
-			if let Err(e) = migration_result {
-				self.connection
-					.exec("rollback", vec![])
-					.await
-					.map_err(|e| Error::Database(e.to_string()))?;
+				Ok::<_, Error>(())
+			})
+			.await;
 
+			if let Err(e) = result {
 				tracing::debug!("Re-applied changes for migration [{name}].");
 
 				return Err(e);
 			}
 
-			self.connection
-				.exec(
-					"delete from migrations where id = ?",
-					vec![Value::U64(record.id)],
-				)
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
-
-			self.connection
-				.exec("commit", vec![])
-				.await
-				.map_err(|e| Error::Database(e.to_string()))?;
-
 			tracing::info!("Successfully rolled back migration [{name}].");
 		}
 
 		Ok(())
 	}
 
-	async fn get_state(conn: &mut Connection) -> Result<Vec<StoredMigration>, Error> {
-		let sql = migrations_table_query();
+	async fn get_state(
+		conn: &mut Connection,
+		connection: &str,
+	) -> Result<Vec<StoredMigration>, Error> {
+		let sql = connection::grammar(connection).compile_migrations_table();
 
-		tracing::debug!(sql = sql, "Running CREATE TABLE IF NOT EXISTS SQL query");
+		tracing::debug!(
+			sql = sql.as_str(),
+			"Running CREATE TABLE IF NOT EXISTS SQL query"
+		);
 
-		conn.exec(sql, vec![])
-			.await
-			.map_err(|e| Error::Database(e.to_string()))?;
+		events::record(connection, &sql, vec![], |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await?;
 
-		Ok(conn
-			.get_values("select * from migrations", vec![])
-			.await
-			.map_err(|e| Error::Database(e.to_string()))?
-			.into_iter()
-			.map(from_value)
-			.collect::<Result<Vec<_>, _>>()?)
+		let sql = "select * from migrations";
+		Ok(events::record(connection, sql, vec![], |bindings| {
+			conn.get_values(sql, bindings)
+		})
+		.await?
+		.into_iter()
+		.map(from_value)
+		.collect::<Result<Vec<_>, _>>()?)
 	}
 }
 
@@ -241,24 +236,3 @@ pub struct StoredMigration {
 	pub batch: u64,
 	pub migration: String,
 }
-
-const fn migrations_table_query() -> &'static str {
-	use crate::connection::Database;
-
-	match connection::which_db() {
-		Database::MySQL => {
-			"create table if not exists migrations (
-                id int unsigned not null auto_increment primary key,
-                migration varchar(255) not null unique,
-                batch int not null
-            )"
-		},
-		Database::PostgreSQL => {
-			"create table if not exists migrations (
-                id serial primary key,
-                migration varchar(255) not null unique,
-                batch int not null
-            )"
-		},
-	}
-}
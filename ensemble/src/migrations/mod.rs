@@ -3,13 +3,10 @@ use async_trait::async_trait;
 use crate::connection::ConnectError;
 use std::fmt::Debug;
 
-#[cfg(any(feature = "mysql", feature = "postgres"))]
 pub use {migrator::Migrator, schema::Schema};
 
-#[cfg(any(feature = "mysql", feature = "postgres"))]
 mod migrator;
 
-#[cfg(any(feature = "mysql", feature = "postgres"))]
 /// The migration schema.
 pub mod schema;
 
@@ -24,6 +21,10 @@ pub enum Error {
 	#[error("{0}")]
 	Database(String),
 
+	/// A query run by the migrations failed.
+	#[error(transparent)]
+	Query(crate::Error),
+
 	/// The migration could not be found.
 	#[error("Could not locate the {0} migration.")]
 	NotFound(String),
@@ -32,7 +33,7 @@ pub enum Error {
 	#[error("Failed to receive column in schema.")]
 	SendColumn,
 
-	/// One of the migrations locked the connection.
+	/// A schema change was attempted outside of a running migration.
 	#[error("Failed to obtain connection")]
 	Lock,
 
@@ -41,6 +42,15 @@ pub enum Error {
 	Decode(#[from] rbs::Error),
 }
 
+impl From<crate::Error> for Error {
+	fn from(error: crate::Error) -> Self {
+		match error {
+			crate::Error::Connection(error) => Self::Connection(error),
+			error => Self::Query(error),
+		}
+	}
+}
+
 /// Accepts a list of structs that implement the [`Migration`] trait, and runs them.
 #[macro_export]
 macro_rules! migrate {
@@ -1,14 +1,13 @@
 use inflector::Inflector;
 use itertools::{Either, Itertools};
-use rbs::Value;
 use std::{any::type_name, sync::mpsc};
 
-use self::{
+pub use self::{
 	column::{Column, Type},
-	command::{Command, ForeignIndex},
+	command::{ForeignIndex, OnAction},
 };
-use super::{migrator::MIGRATE_CONN, Error};
-use crate::{connection, Model};
+use super::{migrator, Error};
+use crate::{connection, events, grammar::Grammar, Model};
 
 mod column;
 mod command;
@@ -17,7 +16,7 @@ pub struct Schema {}
 
 pub enum Schemable {
 	Column(Column),
-	Command(Command),
+	Foreign(ForeignIndex),
 }
 
 impl Schema {
@@ -26,44 +25,21 @@ impl Schema {
 	/// # Errors
 	///
 	/// Returns an error if the table cannot be created, or if a connection to the database cannot be established.
-	#[allow(clippy::unused_async)]
 	pub async fn create<F>(table_name: &str, callback: F) -> Result<(), Error>
 	where
 		F: FnOnce(&mut Table) + Send,
 	{
-		let (columns, commands) = Self::get_schema(table_name.to_string(), callback)?;
-		let mut conn_lock = MIGRATE_CONN.try_lock().map_err(|_| Error::Lock)?;
-		let mut conn = conn_lock.take().ok_or(Error::Lock)?;
-
-		let sql = format!(
-			"CREATE TABLE {} ({}) {}; {}",
-			table_name,
-			columns
-				.iter()
-				.map(Column::to_sql)
-				.chain(commands.iter().map(|cmd| cmd.inline_sql.clone()))
-				.join(", "),
-			if connection::which_db().is_mysql() {
-				"ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"
-			} else {
-				""
-			},
-			commands
-				.iter()
-				.filter_map(|cmd| cmd.post_sql.clone())
-				.join("\n")
-		);
+		let connection = migrator::current_connection()?;
+		let mut conn = connection::get(&connection).await?;
+		let sql = Self::to_sql(connection::grammar(&connection), table_name, callback)?;
 
 		tracing::debug!(sql = sql.as_str(), "Running CREATE TABLE SQL query");
-		let query_result = conn.exec(&sql, vec![]).await;
+		events::record(&connection, &sql, vec![], |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await?;
 
-		conn_lock.replace(conn);
-		drop(conn_lock);
-
-		match query_result {
-			Ok(_) => Ok(()),
-			Err(e) => Err(Error::Database(e.to_string())),
-		}
+		Ok(())
 	}
 
 	/// Drops a table.
@@ -72,24 +48,37 @@ impl Schema {
 	///
 	/// Returns an error if the table cannot be dropped, or if a connection to the database cannot be established.
 	pub async fn drop(table_name: &str) -> Result<(), Error> {
-		let mut conn_lock = MIGRATE_CONN.try_lock().map_err(|_| Error::Lock)?;
-		let mut conn = conn_lock.take().ok_or(Error::Lock)?;
+		let connection = migrator::current_connection()?;
+		let mut conn = connection::get(&connection).await?;
+		let sql = connection::grammar(&connection).compile_drop(table_name);
 
-		let (sql, bindings) = ("DROP TABLE ?", vec![Value::String(table_name.to_string())]);
+		tracing::debug!(sql = sql.as_str(), "Running DROP TABLE SQL query");
+		events::record(&connection, &sql, vec![], |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await?;
 
-		tracing::debug!(sql = sql, bindings = ?bindings, "Running DROP TABLE SQL query");
-		let query_result = conn.exec(sql, bindings).await;
+		Ok(())
+	}
 
-		conn_lock.replace(conn);
-		drop(conn_lock);
+	/// Get the SQL that [`Schema::create`] would run to create the table, compiled with the given grammar.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the table definition cannot be collected.
+	pub fn to_sql<F>(grammar: &dyn Grammar, table_name: &str, callback: F) -> Result<String, Error>
+	where
+		F: FnOnce(&mut Table),
+	{
+		let (columns, foreign) = Self::get_schema(table_name.to_string(), callback)?;
 
-		match query_result {
-			Ok(_) => Ok(()),
-			Err(e) => Err(Error::Database(e.to_string())),
-		}
+		Ok(grammar.compile_create(table_name, &columns, &foreign))
 	}
 
-	fn get_schema<F>(table_name: String, callback: F) -> Result<(Vec<Column>, Vec<Command>), Error>
+	fn get_schema<F>(
+		table_name: String,
+		callback: F,
+	) -> Result<(Vec<Column>, Vec<ForeignIndex>), Error>
 	where
 		F: FnOnce(&mut Table),
 	{
@@ -118,7 +107,7 @@ impl Schema {
 			.into_iter()
 			.map(|part| match part {
 				Schemable::Column(col) => Either::Left(col),
-				Schemable::Command(cmd) => Either::Right(cmd),
+				Schemable::Foreign(index) => Either::Right(index),
 			})
 			.partition_map(|part| part))
 	}
@@ -133,19 +122,10 @@ pub struct Table {
 impl Table {
 	/// Creates a primary key incrementing integer column called `id`.
 	pub fn id(&mut self) -> Column {
-		let column = Column::new("id".to_string(), Type::BigInteger, self.sender.clone())
+		Column::new("id".to_string(), Type::BigInteger, self.sender.clone())
 			.primary(true)
-			.increments(true);
-
-		#[cfg(feature = "mysql")]
-		{
-			column.unsigned(true)
-		}
-
-		#[cfg(not(feature = "mysql"))]
-		{
-			column
-		}
+			.increments(true)
+			.unsigned(true)
 	}
 
 	/// Create a primary key UUID column called `id`.
@@ -190,7 +170,6 @@ impl Table {
 		ForeignIndex::new(column.to_string(), self.name.clone(), self.sender.clone())
 	}
 
-	#[cfg(feature = "mysql")]
 	/// Create a new enum column on the table.
 	pub fn r#enum(&mut self, name: &str, values: &[&str]) -> Column {
 		Column::new(
@@ -208,13 +187,7 @@ impl Table {
 		let column = format!("{}_{}", M::NAME, M::PRIMARY_KEY).to_snake_case();
 
 		if ["u64", "u32", "u16", "u8", "usize"].contains(&type_name::<M::PrimaryKey>()) {
-			#[allow(unused_variables)]
-			let column = Column::new(column.clone(), Type::BigInteger, self.sender.clone());
-
-			#[cfg(feature = "mysql")]
-			{
-				column.unsigned(true);
-			};
+			Column::new(column.clone(), Type::BigInteger, self.sender.clone()).unsigned(true);
 		} else {
 			Column::new(column.clone(), Type::String(255), self.sender.clone());
 		}
@@ -225,13 +198,7 @@ impl Table {
 
 	/// Create a foreign ID column for the given model.
 	pub fn foreign_id(&mut self, name: &str) -> ForeignIndex {
-		#[allow(unused_variables)]
-		let column = Column::new(name.to_string(), Type::BigInteger, self.sender.clone());
-
-		#[cfg(feature = "mysql")]
-		{
-			column.unsigned(true);
-		};
+		Column::new(name.to_string(), Type::BigInteger, self.sender.clone()).unsigned(true);
 
 		let index = ForeignIndex::new(name.to_string(), self.name.clone(), self.sender.clone());
 
@@ -262,12 +229,8 @@ impl Table {
 			.nullable(true)
 			.use_current(true);
 
-		#[allow(unused_variables)]
-		let updated_at = self.timestamp("updated_at").nullable(true);
-
-		#[cfg(feature = "mysql")]
-		{
-			updated_at.use_current_on_update(true);
-		}
+		self.timestamp("updated_at")
+			.nullable(true)
+			.use_current_on_update(true);
 	}
 }
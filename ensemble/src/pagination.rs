@@ -0,0 +1,636 @@
+//! Paginated query results.
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rbs::{value::map::ValueMap, Value};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt::Display, str::FromStr};
+
+use crate::Error;
+
+/// A page of results, along with the total number of results. Returned by [`Builder::paginate`](crate::query::Builder::paginate).
+///
+/// Serializes to the same `{ data, links, meta }` shape as Laravel's paginated API resources,
+/// so it can be returned from a handler as-is.
+#[derive(Debug, Clone)]
+pub struct Paginator<M> {
+	/// The results on the current page.
+	pub items: Vec<M>,
+	/// The total number of results, across every page.
+	pub total: u64,
+	/// The maximum number of results on each page.
+	pub per_page: u64,
+	/// The number of the current page, starting at 1.
+	pub current_page: u64,
+	/// The number of the last page.
+	pub last_page: u64,
+	path: String,
+}
+
+impl<M> Paginator<M> {
+	pub(crate) fn new(items: Vec<M>, total: u64, current_page: u64, per_page: u64) -> Self {
+		Self {
+			items,
+			total,
+			per_page,
+			current_page,
+			last_page: total.div_ceil(per_page).max(1),
+			path: String::new(),
+		}
+	}
+
+	/// Set the path the page links point to, like `/users`. By default, the links only contain the query string (like `?page=2`).
+	#[must_use]
+	pub fn with_path(mut self, path: &str) -> Self {
+		self.path = path.to_string();
+		self
+	}
+
+	/// Whether there are more results after the current page.
+	#[must_use]
+	pub const fn has_more_pages(&self) -> bool {
+		self.current_page < self.last_page
+	}
+
+	/// The URL of the given page.
+	#[must_use]
+	pub fn url(&self, page: u64) -> String {
+		page_url(&self.path, page)
+	}
+}
+
+impl<M: Serialize> Serialize for Paginator<M> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let (from, to) = bounds(self.current_page, self.per_page, self.items.len());
+
+		Page {
+			data: &self.items,
+			links: Links {
+				first: Some(self.url(1)),
+				last: Some(self.url(self.last_page)),
+				prev: (self.current_page > 1).then(|| self.url(self.current_page - 1)),
+				next: self
+					.has_more_pages()
+					.then(|| self.url(self.current_page + 1)),
+			},
+			meta: Meta {
+				current_page: self.current_page,
+				from,
+				last_page: Some(self.last_page),
+				path: &self.path,
+				per_page: self.per_page,
+				to,
+				total: Some(self.total),
+			},
+		}
+		.serialize(serializer)
+	}
+}
+
+/// A page of results, without the total number of results. Returned by [`Builder::simple_paginate`](crate::query::Builder::simple_paginate).
+///
+/// Serializes to the same `{ data, links, meta }` shape as Laravel's simple paginated API resources.
+#[derive(Debug, Clone)]
+pub struct SimplePaginator<M> {
+	/// The results on the current page.
+	pub items: Vec<M>,
+	/// The maximum number of results on each page.
+	pub per_page: u64,
+	/// The number of the current page, starting at 1.
+	pub current_page: u64,
+	/// Whether there are more results after the current page.
+	pub has_more_pages: bool,
+	path: String,
+}
+
+impl<M> SimplePaginator<M> {
+	pub(crate) const fn new(
+		items: Vec<M>,
+		current_page: u64,
+		per_page: u64,
+		has_more_pages: bool,
+	) -> Self {
+		Self {
+			items,
+			per_page,
+			current_page,
+			has_more_pages,
+			path: String::new(),
+		}
+	}
+
+	/// Set the path the page links point to, like `/users`. By default, the links only contain the query string (like `?page=2`).
+	#[must_use]
+	pub fn with_path(mut self, path: &str) -> Self {
+		self.path = path.to_string();
+		self
+	}
+
+	/// The URL of the given page.
+	#[must_use]
+	pub fn url(&self, page: u64) -> String {
+		page_url(&self.path, page)
+	}
+}
+
+impl<M: Serialize> Serialize for SimplePaginator<M> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let (from, to) = bounds(self.current_page, self.per_page, self.items.len());
+
+		Page {
+			data: &self.items,
+			links: Links {
+				first: Some(self.url(1)),
+				last: None,
+				prev: (self.current_page > 1).then(|| self.url(self.current_page - 1)),
+				next: self.has_more_pages.then(|| self.url(self.current_page + 1)),
+			},
+			meta: Meta {
+				current_page: self.current_page,
+				from,
+				last_page: None,
+				path: &self.path,
+				per_page: self.per_page,
+				to,
+				total: None,
+			},
+		}
+		.serialize(serializer)
+	}
+}
+
+/// A page of results, and the cursors pointing to the pages around it. Returned by [`Builder::cursor_paginate`](crate::query::Builder::cursor_paginate).
+///
+/// Serializes to the same `{ data, links, meta }` shape as Laravel's cursor paginated API resources.
+#[derive(Debug, Clone)]
+pub struct CursorPaginator<M> {
+	/// The results on the current page.
+	pub items: Vec<M>,
+	/// The maximum number of results on each page.
+	pub per_page: u64,
+	/// The cursor pointing to the next page, if there is one.
+	pub next_cursor: Option<Cursor>,
+	/// The cursor pointing to the previous page, if there is one.
+	pub prev_cursor: Option<Cursor>,
+	path: String,
+}
+
+impl<M> CursorPaginator<M> {
+	pub(crate) const fn new(
+		items: Vec<M>,
+		per_page: u64,
+		next_cursor: Option<Cursor>,
+		prev_cursor: Option<Cursor>,
+	) -> Self {
+		Self {
+			items,
+			per_page,
+			next_cursor,
+			prev_cursor,
+			path: String::new(),
+		}
+	}
+
+	/// Set the path the page links point to, like `/users`. By default, the links only contain the query string (like `?cursor=...`).
+	#[must_use]
+	pub fn with_path(mut self, path: &str) -> Self {
+		self.path = path.to_string();
+		self
+	}
+
+	/// The URL of the page the given cursor points to.
+	#[must_use]
+	pub fn url(&self, cursor: &Cursor) -> String {
+		format!("{}?cursor={}", self.path, cursor.encode())
+	}
+}
+
+impl<M: Serialize> Serialize for CursorPaginator<M> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		CursorPage {
+			data: &self.items,
+			links: Links {
+				first: None,
+				last: None,
+				prev: self.prev_cursor.as_ref().map(|cursor| self.url(cursor)),
+				next: self.next_cursor.as_ref().map(|cursor| self.url(cursor)),
+			},
+			meta: CursorMeta {
+				path: &self.path,
+				per_page: self.per_page,
+				next_cursor: self.next_cursor.as_ref(),
+				prev_cursor: self.prev_cursor.as_ref(),
+			},
+		}
+		.serialize(serializer)
+	}
+}
+
+/// The position of a row in the results of a query, which cursor pagination continues from.
+///
+/// Cursors are meant to be handed to clients as opaque, URL-safe strings, with [`encode`](Self::encode) and [`decode`](Self::decode).
+/// They also serialize to (and deserialize from) their encoded form, so they can be used directly in request parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+	/// The values of the columns the results are ordered by, on the row the cursor points from.
+	parameters: Vec<(String, Value)>,
+	points_to_next_items: bool,
+}
+
+/// The extension types (like timestamps and UUIDs) cursor values can have, which are kept so the values are bound to queries with the right type.
+const EXT_TYPES: &[&str] = &[
+	"Date",
+	"DateTime",
+	"Decimal",
+	"Json",
+	"Time",
+	"Timestamp",
+	"Uuid",
+];
+
+impl Cursor {
+	/// Create a cursor pointing from the given row, which must contain every one of the given columns.
+	pub(crate) fn from_row<'a>(
+		row: &Value,
+		columns: impl IntoIterator<Item = &'a str>,
+		points_to_next_items: bool,
+	) -> Result<Self, Error> {
+		let row = row.as_map().ok_or(Error::InvalidQuery)?;
+
+		let mut parameters = columns
+			.into_iter()
+			.map(|column| {
+				// the results can't be paginated by a column they don't include
+				let (_, value) = row
+					.0
+					.iter()
+					.find(|(key, _)| key.as_str() == Some(column))
+					.ok_or(Error::InvalidQuery)?;
+
+				Ok((column.to_string(), value.clone()))
+			})
+			.collect::<Result<Vec<_>, Error>>()?;
+		// decoded cursors have their parameters sorted by column, so equal cursors compare as equal
+		parameters.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+		Ok(Self {
+			parameters,
+			points_to_next_items,
+		})
+	}
+
+	/// The value of the given column on the row the cursor points from.
+	#[must_use]
+	pub fn parameter(&self, column: &str) -> Option<&Value> {
+		self.parameters
+			.iter()
+			.find(|(name, _)| name == column)
+			.map(|(_, value)| value)
+	}
+
+	/// Whether the cursor points to the results after its row, or to the ones before it.
+	#[must_use]
+	pub const fn points_to_next_items(&self) -> bool {
+		self.points_to_next_items
+	}
+
+	/// Encode the cursor as an opaque, URL-safe string.
+	#[must_use]
+	pub fn encode(&self) -> String {
+		let mut object = serde_json::Map::new();
+		let mut types = serde_json::Map::new();
+
+		for (column, value) in &self.parameters {
+			let value = if let Value::Ext(r#type, value) = value {
+				types.insert(column.clone(), (*r#type).into());
+				value
+			} else {
+				value
+			};
+
+			object.insert(column.clone(), to_json(value));
+		}
+
+		object.insert(
+			"_pointsToNextItems".to_string(),
+			self.points_to_next_items.into(),
+		);
+		if !types.is_empty() {
+			object.insert("_types".to_string(), types.into());
+		}
+
+		URL_SAFE_NO_PAD.encode(serde_json::Value::Object(object).to_string())
+	}
+
+	/// Decode a cursor previously encoded with [`encode`](Self::encode).
+	///
+	/// # Errors
+	///
+	/// Returns [`Error::InvalidCursor`] if the string isn't a valid cursor.
+	pub fn decode(encoded: &str) -> Result<Self, Error> {
+		let json = URL_SAFE_NO_PAD
+			.decode(encoded)
+			.map_err(|_| Error::InvalidCursor)?;
+
+		let Ok(serde_json::Value::Object(mut object)) = serde_json::from_slice(&json) else {
+			return Err(Error::InvalidCursor);
+		};
+
+		let Some(serde_json::Value::Bool(points_to_next_items)) =
+			object.remove("_pointsToNextItems")
+		else {
+			return Err(Error::InvalidCursor);
+		};
+
+		let types = match object.remove("_types") {
+			None => serde_json::Map::new(),
+			Some(serde_json::Value::Object(types)) => types,
+			Some(_) => return Err(Error::InvalidCursor),
+		};
+
+		let mut parameters = object
+			.into_iter()
+			.map(|(column, value)| (column, from_json(value)))
+			.collect::<Vec<_>>();
+
+		for (column, r#type) in types {
+			let r#type = EXT_TYPES
+				.iter()
+				.find(|ext| r#type.as_str() == Some(**ext))
+				.ok_or(Error::InvalidCursor)?;
+
+			let (_, value) = parameters
+				.iter_mut()
+				.find(|(name, _)| *name == column)
+				.ok_or(Error::InvalidCursor)?;
+
+			*value = Value::Ext(r#type, Box::new(std::mem::take(value)));
+		}
+
+		Ok(Self {
+			parameters,
+			points_to_next_items,
+		})
+	}
+}
+
+/// Convert a value to JSON, for encoding it in a cursor.
+fn to_json(value: &Value) -> serde_json::Value {
+	match value {
+		Value::Null => serde_json::Value::Null,
+		Value::Bool(bool) => (*bool).into(),
+		Value::I32(number) => (*number).into(),
+		Value::I64(number) => (*number).into(),
+		Value::U32(number) => (*number).into(),
+		Value::U64(number) => (*number).into(),
+		Value::F32(number) => (*number).into(),
+		Value::F64(number) => (*number).into(),
+		Value::String(string) => string.clone().into(),
+		Value::Binary(bytes) => bytes.clone().into(),
+		Value::Array(values) => values.iter().map(to_json).collect(),
+		Value::Map(map) => map
+			.0
+			.iter()
+			.map(|(key, value)| {
+				let key = key
+					.as_str()
+					.map_or_else(|| key.to_string(), ToString::to_string);
+				(key, to_json(value))
+			})
+			.collect::<serde_json::Map<_, _>>()
+			.into(),
+		Value::Ext(_, value) => to_json(value),
+	}
+}
+
+/// Convert a value decoded from a cursor back from JSON.
+fn from_json(value: serde_json::Value) -> Value {
+	match value {
+		serde_json::Value::Null => Value::Null,
+		serde_json::Value::Bool(bool) => Value::Bool(bool),
+		serde_json::Value::Number(number) => number.as_u64().map_or_else(
+			|| {
+				number.as_i64().map_or_else(
+					|| Value::F64(number.as_f64().unwrap_or_default()),
+					Value::I64,
+				)
+			},
+			Value::U64,
+		),
+		serde_json::Value::String(string) => Value::String(string),
+		serde_json::Value::Array(values) => {
+			Value::Array(values.into_iter().map(from_json).collect())
+		},
+		serde_json::Value::Object(map) => Value::Map(ValueMap(
+			map.into_iter()
+				.map(|(key, value)| (Value::String(key), from_json(value)))
+				.collect(),
+		)),
+	}
+}
+
+impl Display for Cursor {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.encode())
+	}
+}
+
+impl FromStr for Cursor {
+	type Err = Error;
+
+	fn from_str(encoded: &str) -> Result<Self, Self::Err> {
+		Self::decode(encoded)
+	}
+}
+
+impl Serialize for Cursor {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&self.encode())
+	}
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let encoded = String::deserialize(deserializer)?;
+
+		Self::decode(&encoded).map_err(serde::de::Error::custom)
+	}
+}
+
+#[derive(Serialize)]
+struct Page<'a, M> {
+	data: &'a [M],
+	links: Links,
+	meta: Meta<'a>,
+}
+
+#[derive(Serialize)]
+struct CursorPage<'a, M> {
+	data: &'a [M],
+	links: Links,
+	meta: CursorMeta<'a>,
+}
+
+#[derive(Serialize)]
+struct Links {
+	first: Option<String>,
+	last: Option<String>,
+	prev: Option<String>,
+	next: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Meta<'a> {
+	current_page: u64,
+	from: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	last_page: Option<u64>,
+	path: &'a str,
+	per_page: u64,
+	to: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	total: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct CursorMeta<'a> {
+	path: &'a str,
+	per_page: u64,
+	next_cursor: Option<&'a Cursor>,
+	prev_cursor: Option<&'a Cursor>,
+}
+
+fn page_url(path: &str, page: u64) -> String {
+	format!("{path}?page={page}")
+}
+
+/// The positions of the first and last results on the page, counting from 1.
+const fn bounds(current_page: u64, per_page: u64, count: usize) -> (Option<u64>, Option<u64>) {
+	if count == 0 {
+		return (None, None);
+	}
+
+	let from = (current_page - 1) * per_page + 1;
+
+	(Some(from), Some(from + count as u64 - 1))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn serializes_in_the_laravel_shape() {
+		let paginator = Paginator::new(vec![4, 5, 6], 7, 2, 3).with_path("/numbers");
+
+		assert_eq!(
+			serde_json::to_value(&paginator).unwrap(),
+			json!({
+				"data": [4, 5, 6],
+				"links": {
+					"first": "/numbers?page=1",
+					"last": "/numbers?page=3",
+					"prev": "/numbers?page=1",
+					"next": "/numbers?page=3",
+				},
+				"meta": {
+					"current_page": 2,
+					"from": 4,
+					"last_page": 3,
+					"path": "/numbers",
+					"per_page": 3,
+					"to": 6,
+					"total": 7,
+				},
+			})
+		);
+	}
+
+	#[test]
+	fn serializes_simple_pages_without_totals() {
+		let paginator = SimplePaginator::<u64>::new(vec![], 1, 3, false);
+
+		assert_eq!(
+			serde_json::to_value(&paginator).unwrap(),
+			json!({
+				"data": [],
+				"links": { "first": "?page=1", "last": null, "prev": null, "next": null },
+				"meta": { "current_page": 1, "from": null, "path": "", "per_page": 3, "to": null },
+			})
+		);
+	}
+
+	#[test]
+	fn round_trips_cursors() {
+		let row = rbs::to_value(json!({ "id": 3, "name": "Carol", "email": "carol@example.com" }))
+			.unwrap();
+		let cursor = Cursor::from_row(&row, ["name", "id"], true).unwrap();
+
+		assert_eq!(cursor.parameter("name"), Some(&Value::from("Carol")));
+		assert_eq!(cursor.parameter("email"), None);
+		assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+		assert!(!cursor.encode().contains(['=', '+', '/']));
+
+		assert!(matches!(
+			Cursor::from_row(&row, ["age"], true),
+			Err(Error::InvalidQuery)
+		));
+		assert!(matches!(
+			Cursor::decode("not a cursor"),
+			Err(Error::InvalidCursor)
+		));
+		assert!(matches!(
+			Cursor::decode(&URL_SAFE_NO_PAD.encode(r#"{"id":3}"#)),
+			Err(Error::InvalidCursor)
+		));
+		assert!(matches!(
+			Cursor::decode(
+				&URL_SAFE_NO_PAD
+					.encode(r#"{"id":3,"_pointsToNextItems":true,"_types":{"id":"Unknown"}}"#)
+			),
+			Err(Error::InvalidCursor)
+		));
+	}
+
+	#[test]
+	fn keeps_the_type_of_extension_values() {
+		let created_at = Value::Ext("DateTime", Box::new(Value::from("2023-08-30T12:00:00Z")));
+		let row = Value::Map(ValueMap(vec![
+			(Value::from("created_at"), created_at.clone()),
+			(Value::from("id"), Value::U64(3)),
+		]));
+
+		let cursor = Cursor::from_row(&row, ["created_at", "id"], false).unwrap();
+		let decoded = Cursor::decode(&cursor.encode()).unwrap();
+
+		assert_eq!(decoded, cursor);
+		assert_eq!(decoded.parameter("created_at"), Some(&created_at));
+		assert_eq!(decoded.parameter("id"), Some(&Value::U64(3)));
+	}
+
+	#[test]
+	fn serializes_cursor_pages_with_cursor_links() {
+		let cursor =
+			Cursor::from_row(&rbs::to_value(json!({ "id": 3 })).unwrap(), ["id"], true).unwrap();
+		let paginator =
+			CursorPaginator::new(vec![2, 3], 2, Some(cursor.clone()), None).with_path("/numbers");
+
+		assert_eq!(
+			serde_json::to_value(&paginator).unwrap(),
+			json!({
+				"data": [2, 3],
+				"links": {
+					"first": null,
+					"last": null,
+					"prev": null,
+					"next": format!("/numbers?cursor={}", cursor.encode()),
+				},
+				"meta": {
+					"path": "/numbers",
+					"per_page": 2,
+					"next_cursor": cursor.encode(),
+					"prev_cursor": null,
+				},
+			})
+		);
+	}
+}
@@ -1,38 +1,63 @@
+use futures::{stream, Stream, TryStreamExt};
 use itertools::Itertools;
 use rbs::Value;
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 use std::{
 	collections::{HashMap, HashSet},
-	fmt::Display,
+	fmt::{Display, Write},
+	future::Future,
+	pin::pin,
 };
 
 use crate::{
-	connection::{self, Database},
-	value, Error, Model,
+	connection, events,
+	grammar::Grammar,
+	pagination::{Cursor, CursorPaginator, Paginator, SimplePaginator},
+	retry, value, Error, Model,
 };
 
 /// The Query Builder.
 #[derive(Debug)]
 pub struct Builder {
-	table: String,
+	pub(crate) table: String,
+	ctes: Vec<Cte>,
+	from_sub: Option<(Box<Self>, String)>,
+	connection: String,
+	use_write_connection: bool,
+	distinct: bool,
+	columns: Vec<Selection>,
 	join: Vec<Join>,
 	order: Vec<Order>,
 	limit: Option<usize>,
 	offset: Option<usize>,
 	r#where: Vec<WhereClause>,
+	group: Vec<String>,
+	having: Vec<WhereClause>,
+	unions: Vec<Union>,
 	eager_load: HashSet<String>,
+	relations: Option<fn(&str) -> Self>,
 }
 
 impl Builder {
 	pub(crate) fn new(table: String) -> Self {
 		Self {
 			table,
+			ctes: vec![],
+			from_sub: None,
+			connection: connection::DEFAULT.to_string(),
+			use_write_connection: false,
+			distinct: false,
+			columns: vec![],
 			limit: None,
 			offset: None,
 			join: vec![],
 			order: vec![],
 			r#where: vec![],
+			group: vec![],
+			having: vec![],
+			unions: vec![],
 			eager_load: HashSet::new(),
+			relations: None,
 		}
 	}
 
@@ -47,11 +72,33 @@ impl Builder {
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub async unsafe fn raw_sql(sql: &str, bindings: Vec<Value>) -> Result<Vec<Value>, Error> {
-		let mut conn = connection::get().await?;
+		Self::raw_sql_on(connection::DEFAULT, sql, bindings).await
+	}
 
-		conn.get_values(sql, bindings)
-			.await
-			.map_err(|e| Error::Database(e.to_string()))
+	/// Execute a raw SQL query on the named connection and return the results.
+	///
+	/// Since the query may write, it always runs on the primary, and counts as a write for sticky reads.
+	///
+	/// # Safety
+	///
+	/// This method is unsafe because it allows for arbitrary SQL to be executed, which can lead to SQL injection.
+	/// It is recommended to build queries using the methods provided by the query builder instead.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, or if a connection to the database cannot be established.
+	pub async unsafe fn raw_sql_on(
+		connection: &str,
+		sql: &str,
+		bindings: Vec<Value>,
+	) -> Result<Vec<Value>, Error> {
+		let mut conn = connection::get(connection).await?;
+		connection::record_write(connection);
+
+		events::record(connection, sql, bindings, |bindings| {
+			conn.get_values(sql, bindings)
+		})
+		.await
 	}
 
 	/// Set the table which the query is targeting.
@@ -61,6 +108,145 @@ impl Builder {
 		self
 	}
 
+	/// Select from the results of the given query instead of a table, naming them with the given alias.
+	#[must_use]
+	pub fn from_sub(mut self, query: Self, alias: &str) -> Self {
+		self.from_sub = Some((Box::new(query), alias.to_string()));
+		self
+	}
+
+	/// Set how the relationships of the queried model are resolved, for queries started from a model.
+	#[must_use]
+	pub(crate) fn relations(mut self, resolver: fn(&str) -> Self) -> Self {
+		self.relations = Some(resolver);
+		self
+	}
+
+	/// Add a common table expression to the query, which can then be selected from or joined like a table with the given name.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct Order {
+	/// #   id: u64,
+	/// # }
+	/// let query = Order::query()
+	///     .with_cte(
+	///         "totals",
+	///         Order::query()
+	///             .select(&["user_id"])
+	///             .select_raw("SUM(total) AS spent", vec![])
+	///             .group_by(&["user_id"]),
+	///     )
+	///     .from("totals")
+	///     .r#where("spent", ">", 1000);
+	/// ```
+	#[must_use]
+	pub fn with_cte(mut self, name: &str, query: Self) -> Self {
+		self.ctes.push(Cte {
+			name: name.to_string(),
+			query: Box::new(query),
+			recursive: None,
+		});
+
+		self
+	}
+
+	/// Add a recursive common table expression to the query. Its rows are the ones returned by the `anchor` query,
+	/// combined with the ones returned by the `recursive` query, which can refer to the expression by its name to build on the previous rows.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct Category {
+	/// #   id: u64,
+	/// #   parent_id: Option<u64>,
+	/// # }
+	/// # async fn example() -> Result<(), ensemble::Error> {
+	/// // load category 1 and all of its descendants
+	/// let subtree = Category::query()
+	///     .with_recursive(
+	///         "tree",
+	///         Category::query().r#where("id", "=", 1),
+	///         Category::query()
+	///             .select(&["categories.*"])
+	///             .join("tree", "categories.parent_id", "=", "tree.id"),
+	///     )
+	///     .from("tree")
+	///     .get::<Category>()
+	///     .await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[must_use]
+	pub fn with_recursive(mut self, name: &str, anchor: Self, recursive: Self) -> Self {
+		self.ctes.push(Cte {
+			name: name.to_string(),
+			query: Box::new(anchor),
+			recursive: Some(Box::new(recursive)),
+		});
+
+		self
+	}
+
+	/// Set the database connection the query should run on.
+	#[must_use]
+	pub fn on(mut self, connection: &str) -> Self {
+		self.connection = connection.to_string();
+		self
+	}
+
+	/// Set the columns to be selected, replacing any previously selected ones.
+	///
+	/// Columns that aren't selected are filled with their default values when the rows are turned into models.
+	#[must_use]
+	pub fn select(mut self, columns: &[&str]) -> Self {
+		self.columns.clear();
+
+		self.add_select(columns)
+	}
+
+	/// Add columns to the ones being selected.
+	#[must_use]
+	pub fn add_select(mut self, columns: &[&str]) -> Self {
+		self.columns.extend(
+			columns
+				.iter()
+				.map(|column| Selection::Column((*column).to_string())),
+		);
+
+		self
+	}
+
+	/// Add a raw expression to the columns being selected, like `COUNT(*) AS total`.
+	///
+	/// The expression is inserted into the query as-is, so any user input should be passed as bindings.
+	#[must_use]
+	pub fn select_raw(mut self, expression: &str, bindings: Vec<Value>) -> Self {
+		self.columns
+			.push(Selection::Raw(expression.to_string(), bindings));
+
+		self
+	}
+
+	/// Only return distinct rows.
+	#[must_use]
+	pub const fn distinct(mut self) -> Self {
+		self.distinct = true;
+		self
+	}
+
+	/// Send the query to the primary database, even if it only reads.
+	#[must_use]
+	pub const fn use_write_connection(mut self) -> Self {
+		self.use_write_connection = true;
+		self
+	}
+
 	/// Apply the given callback to the builder if the provided condition is true.
 	#[must_use]
 	pub fn when(mut self, condition: bool, r#fn: impl FnOnce(Self) -> Self) -> Self {
@@ -95,7 +281,7 @@ impl Builder {
 		self.r#where.push(WhereClause::Simple(Where {
 			boolean: Boolean::And,
 			operator: operator.into(),
-			column: Columns::escape(column),
+			column: column.to_string(),
 			value: Some(value::for_db(value).unwrap()),
 		}));
 
@@ -144,7 +330,7 @@ impl Builder {
 			operator: op.into(),
 			boolean: Boolean::Or,
 			value: Some(value.into()),
-			column: Columns::escape(column),
+			column: column.to_string(),
 		}));
 
 		self
@@ -157,7 +343,7 @@ impl Builder {
 			value: None,
 			boolean: Boolean::And,
 			operator: Operator::NotNull,
-			column: Columns::escape(column),
+			column: column.to_string(),
 		}));
 
 		self
@@ -172,7 +358,7 @@ impl Builder {
 		self.r#where.push(WhereClause::Simple(Where {
 			boolean: Boolean::And,
 			operator: Operator::In,
-			column: Columns::escape(column),
+			column: column.to_string(),
 			value: Some(Value::Array(values.into_iter().map(Into::into).collect())),
 		}));
 
@@ -186,7 +372,7 @@ impl Builder {
 			value: None,
 			boolean: Boolean::And,
 			operator: Operator::IsNull,
-			column: Columns::escape(column),
+			column: column.to_string(),
 		}));
 
 		self
@@ -194,22 +380,70 @@ impl Builder {
 
 	/// Add an inner join to the query.
 	#[must_use]
-	pub fn join<Op: Into<Operator>>(
-		mut self,
-		column: &str,
+	pub fn join<Op: Into<Operator>>(self, table: &str, first: &str, op: Op, second: &str) -> Self {
+		self.join_with(table, |join| join.on(first, op, second))
+	}
+
+	/// Add an inner join to the query, with the conditions added by the given closure.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// # }
+	/// let query = User::query().join_with("contacts", |join| {
+	///     join.on("users.id", "=", "contacts.user_id")
+	///         .r#where("contacts.type", "=", "email")
+	/// });
+	/// ```
+	#[must_use]
+	pub fn join_with(self, table: &str, r#fn: impl FnOnce(JoinClause) -> JoinClause) -> Self {
+		self.add_join(JoinType::Inner, table, r#fn(JoinClause::default()))
+	}
+
+	/// Add a left join to the query.
+	#[must_use]
+	pub fn left_join<Op: Into<Operator>>(
+		self,
+		table: &str,
 		first: &str,
 		op: Op,
 		second: &str,
 	) -> Self {
-		self.join.push(Join {
-			operator: op.into(),
-			r#type: JoinType::Inner,
-			first: first.to_string(),
-			second: second.to_string(),
-			column: Columns::escape(column),
-		});
+		self.left_join_with(table, |join| join.on(first, op, second))
+	}
 
-		self
+	/// Add a left join to the query, with the conditions added by the given closure.
+	#[must_use]
+	pub fn left_join_with(self, table: &str, r#fn: impl FnOnce(JoinClause) -> JoinClause) -> Self {
+		self.add_join(JoinType::Left, table, r#fn(JoinClause::default()))
+	}
+
+	/// Add a right join to the query.
+	#[must_use]
+	pub fn right_join<Op: Into<Operator>>(
+		self,
+		table: &str,
+		first: &str,
+		op: Op,
+		second: &str,
+	) -> Self {
+		self.right_join_with(table, |join| join.on(first, op, second))
+	}
+
+	/// Add a right join to the query, with the conditions added by the given closure.
+	#[must_use]
+	pub fn right_join_with(self, table: &str, r#fn: impl FnOnce(JoinClause) -> JoinClause) -> Self {
+		self.add_join(JoinType::Right, table, r#fn(JoinClause::default()))
+	}
+
+	/// Add a cross join to the query.
+	#[must_use]
+	pub fn cross_join(self, table: &str) -> Self {
+		self.add_join(JoinType::Cross, table, JoinClause::default())
 	}
 
 	/// Add an "order by" clause to the query.
@@ -217,164 +451,1065 @@ impl Builder {
 	pub fn order_by<Dir: Into<Direction>>(mut self, column: &str, direction: Dir) -> Self {
 		self.order.push(Order {
 			direction: direction.into(),
-			column: Columns::escape(column),
+			column: column.to_string(),
 		});
 
 		self
 	}
 
-	/// Logically group a set of where clauses.
+	/// Add a "group by" clause to the query.
 	#[must_use]
-	pub fn where_group(mut self, r#fn: impl FnOnce(Self) -> Self) -> Self {
-		let builder = r#fn(Self::new(self.table.clone()));
+	pub fn group_by(mut self, columns: &[&str]) -> Self {
+		self.group
+			.extend(columns.iter().map(|column| (*column).to_string()));
 
-		self.r#where
-			.push(WhereClause::Group(builder.r#where, Boolean::And));
+		self
+	}
+
+	/// Add a "having" clause to the query, which filters the grouped rows.
+	///
+	/// # Panics
+	///
+	/// Panics if the provided value cannot be serialized.
+	#[must_use]
+	pub fn having<T, Op>(mut self, column: &str, operator: Op, value: T) -> Self
+	where
+		Op: Into<Operator>,
+		T: serde::Serialize,
+	{
+		self.having.push(WhereClause::Simple(Where {
+			boolean: Boolean::And,
+			operator: operator.into(),
+			column: column.to_string(),
+			value: Some(value::for_db(value).unwrap()),
+		}));
 
 		self
 	}
 
-	/// Get the SQL representation of the query.
+	/// Add a raw "having" clause to the query, like `SUM(total) > ?`.
+	///
+	/// The expression is inserted into the query as-is, so any user input should be passed as bindings.
 	#[must_use]
-	pub fn to_sql(&self, r#type: Type) -> String {
-		let mut sql = match r#type {
-			Type::Update => String::new(), // handled in update()
-			Type::Delete => format!("DELETE FROM {}", self.table),
-			Type::Select => format!("SELECT * FROM {}", self.table),
-			Type::Count => format!("SELECT COUNT(*) FROM {}", self.table),
-		};
+	pub fn having_raw(mut self, sql: &str, bindings: Vec<Value>) -> Self {
+		self.having.push(WhereClause::Raw {
+			bindings,
+			boolean: Boolean::And,
+			sql: sql.to_string(),
+		});
 
-		if !self.join.is_empty() {
-			for join in &self.join {
-				sql.push_str(&format!(
-					" {} {} ON {} {} {}",
-					join.r#type, join.column, join.first, join.operator, join.second
-				));
-			}
-		}
+		self
+	}
 
-		if !self.r#where.is_empty() {
-			sql.push_str(" WHERE ");
+	/// Add a where clause comparing two columns, like `posts.user_id = users.id`.
+	///
+	/// This is mostly useful in subqueries, to refer to the columns of the outer query.
+	#[must_use]
+	pub fn where_column<Op: Into<Operator>>(mut self, first: &str, op: Op, second: &str) -> Self {
+		self.r#where.push(WhereClause::Column {
+			boolean: Boolean::And,
+			operator: op.into(),
+			first: first.to_string(),
+			second: second.to_string(),
+		});
 
-			for (i, where_clause) in self.r#where.iter().enumerate() {
-				sql.push_str(&where_clause.to_sql(i != 0));
-			}
-		}
+		self
+	}
 
-		if !self.order.is_empty() {
-			sql.push_str(" ORDER BY ");
+	/// Add a "where in" clause to the query, which matches the values returned by the subquery built in the given closure.
+	///
+	/// The closure receives an empty query, so it should set the table with [`from`](Self::from).
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// # }
+	/// let query = User::query().where_in_sub("id", |query| {
+	///     query.from("posts").select(&["user_id"]).r#where("published", "=", true)
+	/// });
+	/// ```
+	#[must_use]
+	pub fn where_in_sub(self, column: &str, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.where_sub(column, Operator::In, r#fn)
+	}
 
-			sql.push_str(
-				&self
-					.order
-					.iter()
-					.map(|order| format!("{} {}", order.column, order.direction))
-					.join(", "),
-			);
-		}
+	/// Add a "where not in" clause to the query, which excludes the values returned by the subquery built in the given closure.
+	#[must_use]
+	pub fn where_not_in_sub(self, column: &str, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.where_sub(column, Operator::NotIn, r#fn)
+	}
 
-		if let Some(take) = self.limit {
-			sql.push_str(&format!(" LIMIT {take}"));
-		}
+	/// Add a where clause comparing a column to the (single) value returned by the subquery built in the given closure.
+	#[must_use]
+	pub fn where_sub<Op: Into<Operator>>(
+		mut self,
+		column: &str,
+		operator: Op,
+		r#fn: impl FnOnce(Self) -> Self,
+	) -> Self {
+		self.r#where.push(WhereClause::Sub {
+			boolean: Boolean::And,
+			operator: operator.into(),
+			column: column.to_string(),
+			query: Box::new(r#fn(Self::new(String::new()))),
+		});
 
-		if let Some(skip) = self.offset {
-			sql.push_str(&format!(" OFFSET {skip}"));
-		}
+		self
+	}
 
-		sql
+	/// Add a "where exists" clause to the query, which matches if the subquery built in the given closure returns any rows.
+	#[must_use]
+	pub fn where_exists(self, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.add_exists(false, r#fn)
 	}
 
-	/// Get the current query value bindings.
+	/// Add a "where not exists" clause to the query, which matches if the subquery built in the given closure returns no rows.
 	#[must_use]
-	pub fn get_bindings(&self) -> Vec<Value> {
-		self.r#where
-			.iter()
-			.flat_map(WhereClause::get_bindings)
-			.collect()
+	pub fn where_not_exists(self, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.add_exists(true, r#fn)
 	}
 
-	/// Retrieve the number of records that match the query constraints.
+	/// Only return models that have at least one related model in the given relationship, matching the constraints added in the given closure.
 	///
-	/// # Errors
+	/// # Panics
 	///
-	/// Returns an error if the query fails, or if a connection to the database cannot be established.
-	pub async fn count(self) -> Result<u64, Error> {
-		let mut conn = connection::get().await?;
-
-		let values = conn
-			.get_values(&self.to_sql(Type::Count), self.get_bindings())
-			.await
-			.map_err(|e| Error::Database(e.to_string()))?;
-
-		values
-			.first()
-			.and_then(|m| m.as_map())
-			.and_then(|m| m.first())
-			.and_then(|(_, v)| v.as_u64())
-			.ok_or_else(|| {
-				Error::Serialization(rbs::value::ext::Error::Syntax(
-					"Failed to parse count value".to_string(),
-				))
-			})
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::{Model, relationships::HasMany};
+	/// # #[derive(Debug, Model)]
+	/// # struct Post {
+	/// #   id: u64,
+	/// #   published: bool,
+	/// # }
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// #   posts: HasMany<User, Post>,
+	/// # }
+	/// let query = User::query().where_has("posts", |query| query.r#where("published", "=", true));
+	/// ```
+	#[must_use]
+	pub fn where_has(self, relation: &str, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.add_has(relation, Operator::GreaterOrEqual, 1, r#fn)
 	}
 
-	/// Execute the query and return the first result.
+	/// Only return models whose number of related models in the given relationship matches the given comparison, like `has("posts", ">=", 3)`.
 	///
-	/// # Errors
+	/// # Panics
 	///
-	/// Returns an error if the query fails, or if a connection to the database cannot be established.
-	pub async fn first<M: Model>(mut self) -> Result<Option<M>, Error> {
-		self.limit = Some(1);
-		let values = self.get::<M>().await?;
-
-		Ok(values.into_iter().next())
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn has<Op: Into<Operator>>(self, relation: &str, operator: Op, count: u64) -> Self {
+		self.add_has(relation, operator.into(), count, |query| query)
 	}
 
-	/// Execute the query and return the results.
+	/// Only return models that don't have any related models in the given relationship.
 	///
-	/// # Errors
+	/// # Panics
 	///
-	/// Returns an error if the query fails, or if a connection to the database cannot be established.
-	pub async fn get<M: Model>(self) -> Result<Vec<M>, Error> {
-		let mut models = self
-			._get()
-			.await?
-			.into_iter()
-			.map(value::from::<M>)
-			.collect::<Result<Vec<M>, rbs::Error>>()?;
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn doesnt_have(self, relation: &str) -> Self {
+		self.add_has(relation, Operator::LessThan, 1, |query| query)
+	}
 
-		if models.is_empty() || self.eager_load.is_empty() {
-			return Ok(models);
-		}
+	/// Add a column with the number of related models in the given relationship, named like `comments_count`.
+	///
+	/// The value can be received by a field marked with `#[model(aggregate)]`.
+	///
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::{Model, relationships::HasMany};
+	/// # #[derive(Debug, Model)]
+	/// # struct Comment {
+	/// #   id: u64,
+	/// # }
+	/// #[derive(Debug, Model)]
+	/// struct Post {
+	///     id: u64,
+	///     comments: HasMany<Post, Comment>,
+	///     #[model(aggregate)]
+	///     comments_count: u64,
+	/// }
+	///
+	/// let query = Post::query().with_count("comments");
+	/// ```
+	#[must_use]
+	pub fn with_count(self, relation: &str) -> Self {
+		self.add_relation_aggregate(
+			relation,
+			RelationAggregate::Count,
+			format!("{relation}_count"),
+		)
+	}
 
-		let model = M::default();
-		for relation in self.eager_load {
-			tracing::trace!(
-				"Eager loading {relation} relation for {} models",
-				models.len()
-			);
+	/// Add a column with the sum of the given column of the related models, named like `comments_sum_likes`.
+	///
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn with_sum(self, relation: &str, column: &str) -> Self {
+		self.add_relation_function(relation, "SUM", column)
+	}
 
-			let rows = model
-				.eager_load(&relation, models.iter().collect::<Vec<&M>>().as_slice())
-				.get_rows()
-				.await?;
+	/// Add a column with the average of the given column of the related models, named like `comments_avg_likes`.
+	///
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn with_avg(self, relation: &str, column: &str) -> Self {
+		self.add_relation_function(relation, "AVG", column)
+	}
 
-			for model in &mut models {
-				model.fill_relation(&relation, &rows)?;
-			}
-		}
+	/// Add a column with the minimum of the given column of the related models, named like `comments_min_likes`.
+	///
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn with_min(self, relation: &str, column: &str) -> Self {
+		self.add_relation_function(relation, "MIN", column)
+	}
 
-		Ok(models)
+	/// Add a column with the maximum of the given column of the related models, named like `comments_max_likes`.
+	///
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn with_max(self, relation: &str, column: &str) -> Self {
+		self.add_relation_function(relation, "MAX", column)
 	}
 
-	/// Execute the query and return the results as a vector of rows.
+	/// Add a column with whether there are any related models in the given relationship, named like `comments_exists`.
 	///
-	/// # Errors
+	/// # Panics
+	///
+	/// Panics if the query wasn't started from a model, or if the model doesn't have the given relationship.
+	#[must_use]
+	pub fn with_exists(self, relation: &str) -> Self {
+		self.add_relation_aggregate(
+			relation,
+			RelationAggregate::Exists,
+			format!("{relation}_exists"),
+		)
+	}
+
+	/// Combine the results of the query with the ones of the given query, removing duplicate rows.
+	///
+	/// Both queries must select the same number of columns. Any ordering, limit or offset set on this query applies to the combined results.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct Post {
+	/// #   id: u64,
+	/// # }
+	/// # #[derive(Debug, Model)]
+	/// # struct Comment {
+	/// #   id: u64,
+	/// # }
+	/// let query = Post::query()
+	///     .select(&["id", "created_at"])
+	///     .union(Comment::query().select(&["id", "created_at"]))
+	///     .order_by("created_at", "desc")
+	///     .limit(10);
+	/// ```
+	#[must_use]
+	pub fn union(mut self, query: Self) -> Self {
+		self.unions.push(Union {
+			query: Box::new(query),
+			all: false,
+		});
+
+		self
+	}
+
+	/// Combine the results of the query with the ones of the given query, keeping duplicate rows.
+	#[must_use]
+	pub fn union_all(mut self, query: Self) -> Self {
+		self.unions.push(Union {
+			query: Box::new(query),
+			all: true,
+		});
+
+		self
+	}
+
+	/// Logically group a set of where clauses.
+	#[must_use]
+	pub fn where_group(mut self, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		let builder = r#fn(Self::new(self.table.clone()));
+
+		self.r#where
+			.push(WhereClause::Group(builder.r#where, Boolean::And));
+
+		self
+	}
+
+	/// Get the SQL representation of the query, compiled with the given grammar.
+	#[must_use]
+	pub fn to_sql(&self, r#type: Type, grammar: &dyn Grammar) -> String {
+		match r#type {
+			Type::Update => self.compile_clauses(grammar), // handled in update()
+			Type::Delete => grammar.compile_delete(self),
+			Type::Select => grammar.compile_select(self),
+			Type::Count => grammar.compile_count(self),
+		}
+	}
+
+	/// The table the query runs against.
+	#[must_use]
+	pub fn table(&self) -> &str {
+		&self.table
+	}
+
+	/// Compile what the query selects from: either its table, or an aliased subquery.
+	#[must_use]
+	pub fn compile_from<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		match &self.from_sub {
+			Some((query, alias)) => format!(
+				"({}) AS {}",
+				grammar.compile_select(query),
+				grammar.wrap_identifier(alias)
+			),
+			None => self.table.clone(),
+		}
+	}
+
+	/// Compile the columns that should be selected, including the `DISTINCT` keyword if needed.
+	#[must_use]
+	pub fn compile_columns<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		let columns = if self.columns.is_empty() {
+			"*".to_string()
+		} else {
+			self.columns
+				.iter()
+				.map(|column| match column {
+					Selection::Column(column) => grammar.wrap(column),
+					Selection::Raw(expression, _) => expression.clone(),
+					Selection::Aggregate {
+						query,
+						aggregate,
+						alias,
+					} => format!(
+						"({}) AS {}",
+						aggregate.compile(query, grammar),
+						grammar.wrap_identifier(alias)
+					),
+				})
+				.join(", ")
+		};
+
+		if self.distinct {
+			format!("DISTINCT {columns}")
+		} else {
+			columns
+		}
+	}
+
+	/// Compile the joins, where clauses, grouping, ordering and limits of the query, with a leading space.
+	#[must_use]
+	pub fn compile_clauses<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		format!(
+			"{}{}",
+			self.compile_conditions(grammar),
+			self.compile_ordering(grammar)
+		)
+	}
+
+	/// Compile the `WITH` clause of the query (with a trailing space), if it has any common table expressions.
+	#[must_use]
+	pub fn compile_ctes<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		if self.ctes.is_empty() {
+			return String::new();
+		}
+
+		let recursive = if self.ctes.iter().any(|cte| cte.recursive.is_some()) {
+			"RECURSIVE "
+		} else {
+			""
+		};
+
+		let ctes = self
+			.ctes
+			.iter()
+			.map(|cte| {
+				let mut query = grammar.compile_select(&cte.query);
+
+				// the recursive query has to be part of the same select as the anchor, so it's not wrapped like other unions
+				if let Some(recursive) = &cte.recursive {
+					query = format!("{query} UNION ALL {}", grammar.compile_select(recursive));
+				}
+
+				format!("{} AS ({query})", grammar.wrap_identifier(&cte.name))
+			})
+			.join(", ");
+
+		format!("WITH {recursive}{ctes} ")
+	}
+
+	/// Combine the given select with the ones unioned to the query, if any. Each select is wrapped with [`Grammar::wrap_union`].
+	#[must_use]
+	pub fn compile_unions<G: Grammar + ?Sized>(&self, grammar: &G, select: String) -> String {
+		if self.unions.is_empty() {
+			return select;
+		}
+
+		let mut sql = grammar.wrap_union(&select);
+
+		for union in &self.unions {
+			sql.push_str(if union.all { " UNION ALL " } else { " UNION " });
+			sql.push_str(&grammar.wrap_union(&grammar.compile_select(&union.query)));
+		}
+
+		sql
+	}
+
+	/// Whether other queries are unioned to the query.
+	#[must_use]
+	pub const fn has_unions(&self) -> bool {
+		!self.unions.is_empty()
+	}
+
+	/// Whether the query has to be counted from a subquery (because it's unioned, distinct or grouped), since counting it directly wouldn't count its rows.
+	#[must_use]
+	pub const fn counts_subquery(&self) -> bool {
+		self.has_unions() || self.distinct || !self.group.is_empty()
+	}
+
+	/// Compile the joins, where clauses, grouping and having clauses of the query, with a leading space.
+	#[must_use]
+	pub fn compile_conditions<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		let mut sql = String::new();
+
+		for join in &self.join {
+			let _ = write!(sql, " {} {}", join.r#type, grammar.wrap(&join.table));
+
+			if !join.conditions.is_empty() {
+				sql.push_str(" ON ");
+				sql.push_str(&WhereClause::compile_all(&join.conditions, grammar));
+			}
+		}
+
+		if !self.r#where.is_empty() {
+			sql.push_str(" WHERE ");
+			sql.push_str(&WhereClause::compile_all(&self.r#where, grammar));
+		}
+
+		if !self.group.is_empty() {
+			sql.push_str(" GROUP BY ");
+			sql.push_str(
+				&self
+					.group
+					.iter()
+					.map(|column| grammar.wrap(column))
+					.join(", "),
+			);
+		}
+
+		if !self.having.is_empty() {
+			sql.push_str(" HAVING ");
+			sql.push_str(&WhereClause::compile_all(&self.having, grammar));
+		}
+
+		sql
+	}
+
+	/// Compile the ordering, limit and offset of the query, with a leading space.
+	#[must_use]
+	pub fn compile_ordering<G: Grammar + ?Sized>(&self, grammar: &G) -> String {
+		let mut sql = String::new();
+
+		if !self.order.is_empty() {
+			sql.push_str(" ORDER BY ");
+
+			sql.push_str(
+				&self
+					.order
+					.iter()
+					.map(|order| format!("{} {}", grammar.wrap(&order.column), order.direction))
+					.join(", "),
+			);
+		}
+
+		if let Some(take) = self.limit {
+			let _ = write!(sql, " LIMIT {take}");
+		}
+
+		if let Some(skip) = self.offset {
+			let _ = write!(sql, " OFFSET {skip}");
+		}
+
+		sql
+	}
+
+	/// Get the current query value bindings.
+	#[must_use]
+	pub fn get_bindings(&self) -> Vec<Value> {
+		self.cte_bindings()
+			.into_iter()
+			.chain(self.columns.iter().flat_map(Selection::get_bindings))
+			.chain(self.subquery_bindings())
+			.chain(self.clause_bindings())
+			.chain(
+				self.unions
+					.iter()
+					.flat_map(|union| union.query.get_bindings()),
+			)
+			.collect()
+	}
+
+	/// Get the bindings of the joins, where clauses, ordering and limits of the query.
+	fn clause_bindings(&self) -> Vec<Value> {
+		self.join
+			.iter()
+			.flat_map(|join| &join.conditions)
+			.chain(&self.r#where)
+			.chain(&self.having)
+			.flat_map(WhereClause::get_bindings)
+			.collect()
+	}
+
+	/// Get the bindings of aggregate queries, which don't include the selected columns.
+	fn aggregate_bindings(&self) -> Vec<Value> {
+		// aggregates of unions are calculated over the whole select, as a subquery
+		if self.has_unions() {
+			return self.get_bindings();
+		}
+
+		self.cte_bindings()
+			.into_iter()
+			.chain(self.subquery_bindings())
+			.chain(self.clause_bindings())
+			.collect()
+	}
+
+	fn cte_bindings(&self) -> Vec<Value> {
+		self.ctes
+			.iter()
+			.flat_map(|cte| {
+				cte.query
+					.get_bindings()
+					.into_iter()
+					.chain(cte.recursive.iter().flat_map(|query| query.get_bindings()))
+			})
+			.collect()
+	}
+
+	fn subquery_bindings(&self) -> Vec<Value> {
+		self.from_sub
+			.as_ref()
+			.map(|(query, _)| query.get_bindings())
+			.unwrap_or_default()
+	}
+
+	/// Get the bindings of count queries, which only include the selected columns when counting from a subquery.
+	fn count_bindings(&self) -> Vec<Value> {
+		if self.counts_subquery() {
+			self.get_bindings()
+		} else {
+			self.aggregate_bindings()
+		}
+	}
+
+	/// Retrieve the number of records that match the query constraints.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, or if a connection to the database cannot be established.
+	pub async fn count(self) -> Result<u64, Error> {
+		self.count_rows().await
+	}
+
+	async fn count_rows(&self) -> Result<u64, Error> {
+		let values = retry::for_reads(&self.connection)
+			.run("count", || async {
+				let mut conn = self.read_connection().await?;
+				let sql = self.to_sql(Type::Count, connection::grammar(&self.connection));
+				let bindings = self.count_bindings();
+
+				events::record(&self.connection, &sql, bindings, |bindings| {
+					conn.get_values(&sql, bindings)
+				})
+				.await
+			})
+			.await?;
+
+		aggregate_value(values).as_u64().ok_or_else(|| {
+			Error::Serialization(rbs::value::ext::Error::Syntax(
+				"Failed to parse count value".to_string(),
+			))
+		})
+	}
+
+	/// Retrieve the sum of the values of the given column. Returns `None` if no records match the query constraints.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, if a connection to the database cannot be established, or if the result cannot be deserialized into `T`.
+	pub async fn sum<T: DeserializeOwned>(self, column: &str) -> Result<Option<T>, Error> {
+		self.aggregate("SUM", column).await
+	}
+
+	/// Retrieve the average of the values of the given column. Returns `None` if no records match the query constraints.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, if a connection to the database cannot be established, or if the result cannot be deserialized into `T`.
+	pub async fn avg<T: DeserializeOwned>(self, column: &str) -> Result<Option<T>, Error> {
+		self.aggregate("AVG", column).await
+	}
+
+	/// Retrieve the minimum value of the given column. Returns `None` if no records match the query constraints.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, if a connection to the database cannot be established, or if the result cannot be deserialized into `T`.
+	pub async fn min<T: DeserializeOwned>(self, column: &str) -> Result<Option<T>, Error> {
+		self.aggregate("MIN", column).await
+	}
+
+	/// Retrieve the maximum value of the given column. Returns `None` if no records match the query constraints.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, if a connection to the database cannot be established, or if the result cannot be deserialized into `T`.
+	pub async fn max<T: DeserializeOwned>(self, column: &str) -> Result<Option<T>, Error> {
+		self.aggregate("MAX", column).await
+	}
+
+	/// Execute the query and return the first result.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, or if a connection to the database cannot be established.
+	pub async fn first<M: Model>(mut self) -> Result<Option<M>, Error> {
+		self.limit = Some(1);
+		let values = self.get::<M>().await?;
+
+		Ok(values.into_iter().next())
+	}
+
+	/// Get the given page of results, along with the total number of results. Pages start at 1.
+	///
+	/// Any limit or offset previously set on the query is replaced.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `per_page` is zero, if the query fails, or if a connection to the database cannot be established.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// # }
+	/// # async fn example() -> Result<(), ensemble::Error> {
+	/// let page = User::query().order_by("id", "asc").paginate::<User>(2, 15).await?;
+	///
+	/// println!("Showing page {} of {}", page.current_page, page.last_page);
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub async fn paginate<M: Model>(
+		mut self,
+		page: u64,
+		per_page: u64,
+	) -> Result<Paginator<M>, Error> {
+		if per_page == 0 {
+			return Err(Error::InvalidQuery);
+		}
+
+		let page = page.max(1);
+
+		// the ordering doesn't change the total, so it's left out of the count
+		let order = std::mem::take(&mut self.order);
+		self.limit = None;
+		self.offset = None;
+		let total = self.count_rows().await?;
+		self.order = order;
+
+		let items = if total == 0 {
+			vec![]
+		} else {
+			self.for_page(page, per_page)?.get::<M>().await?
+		};
+
+		Ok(Paginator::new(items, total, page, per_page))
+	}
+
+	/// Get the given page of results, without counting the total number of results. Pages start at 1.
+	///
+	/// Fetches one more result than needed to know whether there are more pages, which is cheaper than counting them for large tables.
+	/// Any limit or offset previously set on the query is replaced.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `per_page` is zero or too large, if the query fails, or if a connection to the database cannot be established.
+	pub async fn simple_paginate<M: Model>(
+		self,
+		page: u64,
+		per_page: u64,
+	) -> Result<SimplePaginator<M>, Error> {
+		let page = page.max(1);
+		let mut query = self.for_page(page, per_page)?;
+		let limit = query.limit.unwrap_or_default();
+		query.limit = Some(limit.checked_add(1).ok_or(Error::InvalidQuery)?);
+
+		let mut items = query.get::<M>().await?;
+		let has_more_pages = items.len() > limit;
+		items.truncate(limit);
+
+		Ok(SimplePaginator::new(items, page, per_page, has_more_pages))
+	}
+
+	/// Get the page of results the given cursor points to, or the first page if no cursor is given.
+	///
+	/// Instead of skipping over the previous pages with an offset, the results are filtered to the ones after (or before) the row the cursor points from,
+	/// which stays fast on large tables and doesn't skip or repeat results when rows are added between requests.
+	/// The filter is derived from the query's "order by" clauses (which may mix ascending and descending columns), with the model's primary key added as a tiebreaker.
+	/// Any limit or offset previously set on the query is replaced.
+	///
+	/// The columns the query is ordered by must be included in the results, and can't be nullable.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `per_page` is zero or too large, if the cursor is missing one of the columns the query is ordered by,
+	/// if the query fails, or if a connection to the database cannot be established.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::{Model, pagination::Cursor};
+	/// # #[derive(Debug, Model)]
+	/// # struct Post {
+	/// #   id: u64,
+	/// #   views: u64,
+	/// # }
+	/// # async fn example(cursor: Option<&str>) -> Result<(), ensemble::Error> {
+	/// let cursor = cursor.map(Cursor::decode).transpose()?;
+	/// let page = Post::query()
+	///     .order_by("views", "desc")
+	///     .cursor_paginate::<Post>(15, cursor)
+	///     .await?;
+	///
+	/// if let Some(next) = page.next_cursor {
+	///     println!("More posts at ?cursor={next}");
+	/// }
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub async fn cursor_paginate<M: Model>(
+		mut self,
+		per_page: u64,
+		cursor: Option<Cursor>,
+	) -> Result<CursorPaginator<M>, Error> {
+		let limit = usize::try_from(per_page).map_err(|_| Error::InvalidQuery)?;
+		if limit == 0 {
+			return Err(Error::InvalidQuery);
+		}
+
+		if !self
+			.order
+			.iter()
+			.any(|order| column_name(&order.column) == M::PRIMARY_KEY)
+		{
+			self = self.order_by(M::PRIMARY_KEY, Direction::Ascending);
+		}
+
+		let points_to_next_items = cursor.as_ref().is_none_or(Cursor::points_to_next_items);
+		if let Some(cursor) = &cursor {
+			let clause = keyset_clause(&self.order, cursor)?;
+
+			// the existing conditions are grouped, so the keyset condition applies to all of them (even the ones joined with OR)
+			let conditions = std::mem::take(&mut self.r#where);
+			if !conditions.is_empty() {
+				self.r#where
+					.push(WhereClause::Group(conditions, Boolean::And));
+			}
+
+			self.r#where.push(clause);
+		}
+
+		// the rows before the cursor are fetched in reverse, so the closest ones come first
+		if !points_to_next_items {
+			for order in &mut self.order {
+				order.direction = order.direction.reverse();
+			}
+		}
+
+		let columns = self
+			.order
+			.iter()
+			.map(|order| column_name(&order.column).to_string())
+			.collect::<Vec<_>>();
+
+		self.limit = Some(limit.checked_add(1).ok_or(Error::InvalidQuery)?);
+		self.offset = None;
+
+		let mut items = self.get::<M>().await?;
+		let has_more = items.len() > limit;
+		items.truncate(limit);
+		if !points_to_next_items {
+			items.reverse();
+		}
+
+		let cursor_for = |item: Option<&M>, points_to_next_items: bool| {
+			item.map(|item| {
+				Cursor::from_row(
+					&value::for_db(item)?,
+					columns.iter().map(String::as_str),
+					points_to_next_items,
+				)
+			})
+			.transpose()
+		};
+
+		let next_cursor = if points_to_next_items && !has_more {
+			None
+		} else {
+			cursor_for(items.last(), true)?
+		};
+		let prev_cursor =
+			if points_to_next_items && cursor.is_none() || !points_to_next_items && !has_more {
+				None
+			} else {
+				cursor_for(items.first(), false)?
+			};
+
+		Ok(CursorPaginator::new(
+			items,
+			per_page,
+			next_cursor,
+			prev_cursor,
+		))
+	}
+
+	/// Execute the query and return the results.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, or if a connection to the database cannot be established.
+	pub async fn get<M: Model>(self) -> Result<Vec<M>, Error> {
+		self.fetch().await
+	}
+
+	/// Retrieve the results in chunks of the given size, and pass each chunk to the callback. Return `false` from the callback to stop processing further chunks.
+	///
+	/// Each chunk is fetched with a separate query, using a limit and offset, so only one chunk is kept in memory at a time.
+	/// Relationships eager loaded with [`with`](Self::with) are loaded once per chunk.
+	/// If the query isn't ordered, the results are ordered by the model's primary key, so every chunk is fetched in the same order.
+	///
+	/// If you're updating the rows while chunking them in a way that changes which rows match the query (or their order), use [`chunk_by_id`](Self::chunk_by_id) instead.
+	///
+	/// Returns `false` if the callback stopped the processing early.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `size` is zero, if the callback returns an error, if any of the queries fail, or if a connection to the database cannot be established.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// #   name: String,
+	/// # }
+	/// # async fn example() -> Result<(), ensemble::Error> {
+	/// User::query()
+	///     .chunk(100, |users: Vec<User>| async move {
+	///         for user in users {
+	///             println!("{}", user.name);
+	///         }
+	///
+	///         Ok(true)
+	///     })
+	///     .await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub async fn chunk<M, F, Fut>(self, size: u64, callback: F) -> Result<bool, Error>
+	where
+		M: Model,
+		F: FnMut(Vec<M>) -> Fut,
+		Fut: Future<Output = Result<bool, Error>>,
+	{
+		each_chunk(self.chunks(size)?, callback).await
+	}
+
+	/// Retrieve the results in chunks of the given size ordered by the model's primary key, and pass each chunk to the callback.
+	/// Return `false` from the callback to stop processing further chunks.
+	///
+	/// Instead of an offset, each chunk is fetched with a `where id > ?` condition on the last primary key of the previous chunk,
+	/// so updating or deleting rows during the run doesn't cause rows to be skipped or processed twice.
+	/// Any ordering previously set on the query is replaced. Relationships eager loaded with [`with`](Self::with) are loaded once per chunk.
+	///
+	/// Returns `false` if the callback stopped the processing early.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `size` is zero, if the callback returns an error, if any of the queries fail, or if a connection to the database cannot be established.
+	pub async fn chunk_by_id<M, F, Fut>(self, size: u64, callback: F) -> Result<bool, Error>
+	where
+		M: Model,
+		F: FnMut(Vec<M>) -> Fut,
+		Fut: Future<Output = Result<bool, Error>>,
+	{
+		each_chunk(self.chunks_by_id(size)?, callback).await
+	}
+
+	/// Retrieve the results in chunks of the given size, and pass each model to the callback. Return `false` from the callback to stop processing further models.
+	/// See [`chunk`](Self::chunk) for details on how the chunks are fetched.
+	///
+	/// Returns `false` if the callback stopped the processing early.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `size` is zero, if the callback returns an error, if any of the queries fail, or if a connection to the database cannot be established.
+	pub async fn each<M, F, Fut>(self, size: u64, mut callback: F) -> Result<bool, Error>
+	where
+		M: Model,
+		F: FnMut(M) -> Fut,
+		Fut: Future<Output = Result<bool, Error>>,
+	{
+		let mut chunks = pin!(self.chunks(size)?);
+
+		while let Some(models) = chunks.try_next().await? {
+			for model in models {
+				if !callback(model).await? {
+					return Ok(false);
+				}
+			}
+		}
+
+		Ok(true)
+	}
+
+	/// Retrieve the results as a stream of models, which are fetched in chunks of the given size ordered by the model's primary key.
+	/// See [`chunk_by_id`](Self::chunk_by_id) for details on how the chunks are fetched.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `size` is zero. The stream yields an error if any of the queries fail, or if a connection to the database cannot be established.
+	///
+	/// ## Example
+	///
+	/// ```rust
+	/// # use ensemble::Model;
+	/// # use futures::TryStreamExt;
+	/// # #[derive(Debug, Model)]
+	/// # struct User {
+	/// #   id: u64,
+	/// #   name: String,
+	/// # }
+	/// # async fn example() -> Result<(), ensemble::Error> {
+	/// let mut users = std::pin::pin!(User::query().lazy_by_id::<User>(100)?);
+	///
+	/// while let Some(user) = users.try_next().await? {
+	///     println!("{}", user.name);
+	/// }
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub fn lazy_by_id<M: Model>(
+		self,
+		size: u64,
+	) -> Result<impl Stream<Item = Result<M, Error>> + Send, Error> {
+		Ok(self
+			.chunks_by_id(size)?
+			.map_ok(|models| stream::iter(models.into_iter().map(Ok)))
+			.try_flatten())
+	}
+
+	/// Execute the query and return the models, eager loading the requested relationships.
+	async fn fetch<M: Model>(&self) -> Result<Vec<M>, Error> {
+		// rows that only have some of the columns are filled in with the model's default values
+		let defaults = if self.columns.is_empty() {
+			None
+		} else {
+			Some(value::for_db(M::default())?)
+		};
+
+		let mut models = self
+			.select_values()
+			.await?
+			.into_iter()
+			.map(|row| match &defaults {
+				Some(defaults) => value::from::<M>(fill_defaults(defaults.clone(), row)),
+				None => value::from::<M>(row),
+			})
+			.collect::<Result<Vec<M>, rbs::Error>>()?;
+
+		if models.is_empty() || self.eager_load.is_empty() {
+			return Ok(models);
+		}
+
+		let model = M::default();
+		for relation in &self.eager_load {
+			tracing::trace!(
+				"Eager loading {relation} relation for {} models",
+				models.len()
+			);
+
+			let rows = model
+				.eager_load(relation, models.iter().collect::<Vec<&M>>().as_slice())
+				.get_rows()
+				.await?;
+
+			for model in &mut models {
+				model.fill_relation(relation, &rows)?;
+			}
+		}
+
+		Ok(models)
+	}
+
+	/// Execute the query and deserialize each row into `T`, which can be any type that implements [`Deserialize`](serde::Deserialize),
+	/// like a struct with a field for each selected column or a `HashMap<String, Value>`.
+	///
+	/// Unlike [`get`](Self::get), this doesn't require a model, which makes it useful for grouped and aggregated queries.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the query fails, if a connection to the database cannot be established, or if a row cannot be deserialized into `T`.
+	pub async fn get_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+		Ok(self
+			.select_values()
+			.await?
+			.into_iter()
+			.map(value::from_db::<T>)
+			.collect::<Result<Vec<T>, rbs::Error>>()?)
+	}
+
+	/// Execute the query and return the results as a vector of rows.
+	///
+	/// # Errors
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub(crate) async fn get_rows(&self) -> Result<Vec<HashMap<String, Value>>, Error> {
 		let values = self
-			._get()
+			.select_values()
 			.await?
 			.into_iter()
 			.map(|v| {
@@ -402,29 +1537,32 @@ impl Builder {
 			|| !self.join.is_empty()
 			|| !self.order.is_empty()
 			|| !self.r#where.is_empty()
+			|| !self.group.is_empty()
+			|| !self.having.is_empty()
+			|| self.has_unions()
+			|| !self.ctes.is_empty()
 		{
 			return Err(Error::InvalidQuery);
 		}
 
-		let mut conn = connection::get().await?;
+		let mut conn = self.write_connection().await?;
 		let values: Vec<(String, Value)> = columns.into().0;
 
-		let (sql, bindings) = (
-			format!(
-				"INSERT INTO {} ({}) VALUES ({})",
-				self.table,
-				values.iter().map(|(column, _)| column).join(", "),
-				values.iter().map(|_| "?").join(", ")
-			),
-			values.into_iter().map(|(_, value)| value).collect(),
-		);
+		let (columns, bindings): (Vec<_>, Vec<_>) = values.into_iter().unzip();
+		let sql = connection::grammar(&self.connection).compile_insert(&self.table, &columns);
 
 		tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing INSERT SQL query");
 
-		let result = conn
-			.exec(&sql, bindings)
-			.await
-			.map_err(|e| Error::Database(e.to_string()))?;
+		let result = events::record(&self.connection, &sql, bindings, |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await?;
+		drop(conn);
+
+		// SQLite reports the rowid of the last successful insert on the connection, even if this statement didn't insert anything.
+		if connection::which_db(&self.connection).is_sqlite() && result.rows_affected == 0 {
+			return Ok(None);
+		}
 
 		Ok(rbs::from_value(result.last_insert_id).ok())
 	}
@@ -435,25 +1573,20 @@ impl Builder {
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub async fn increment(self, column: &str, amount: u64) -> Result<u64, Error> {
-		let mut conn = connection::get().await?;
+		let mut conn = self.write_connection().await?;
 		let (sql, mut bindings) = (
-			format!(
-				"UPDATE {} SET {} = {} + ? {}",
-				self.table,
-				Columns::escape(column),
-				Columns::escape(column),
-				self.to_sql(Type::Update)
-			),
-			self.get_bindings(),
+			connection::grammar(&self.connection).compile_increment(&self, column),
+			self.clause_bindings(),
 		);
 		bindings.insert(0, amount.into());
 
 		tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing UPDATE SQL query for increment");
 
-		conn.exec(&sql, bindings)
-			.await
-			.map_err(|e| Error::Database(e.to_string()))
-			.map(|r| r.rows_affected)
+		events::record(&self.connection, &sql, bindings, |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await
+		.map(|r| r.rows_affected)
 	}
 
 	/// Update records in the database. Returns the number of affected rows.
@@ -462,32 +1595,22 @@ impl Builder {
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub async fn update<T: Into<Columns> + Send>(self, values: T) -> Result<u64, Error> {
-		let mut conn = connection::get().await?;
+		let mut conn = self.write_connection().await?;
 		let values: Vec<(String, Value)> = values.into().0;
 
+		let (columns, values): (Vec<_>, Vec<_>) = values.into_iter().unzip();
 		let (sql, bindings) = (
-			format!(
-				"UPDATE {} SET {} {}",
-				self.table,
-				values
-					.iter()
-					.map(|(column, _)| format!("{column} = ?"))
-					.join(", "),
-				self.to_sql(Type::Update)
-			),
-			values
-				.iter()
-				.map(|(_, value)| value.clone())
-				.chain(self.get_bindings())
-				.collect(),
+			connection::grammar(&self.connection).compile_update(&self, &columns),
+			values.into_iter().chain(self.clause_bindings()).collect(),
 		);
 
 		tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing UPDATE SQL query");
 
-		conn.exec(&sql, bindings)
-			.await
-			.map_err(|e| Error::Database(e.to_string()))
-			.map(|r| r.rows_affected)
+		events::record(&self.connection, &sql, bindings, |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await
+		.map(|r| r.rows_affected)
 	}
 
 	/// Delete records from the database. Returns the number of affected rows.
@@ -496,15 +1619,19 @@ impl Builder {
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub async fn delete(self) -> Result<u64, Error> {
-		let mut conn = connection::get().await?;
-		let (sql, bindings) = (self.to_sql(Type::Delete), self.get_bindings());
+		let mut conn = self.write_connection().await?;
+		let (sql, bindings) = (
+			self.to_sql(Type::Delete, connection::grammar(&self.connection)),
+			self.clause_bindings(),
+		);
 
 		tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing DELETE SQL query");
 
-		conn.exec(&sql, bindings)
-			.await
-			.map_err(|e| Error::Database(e.to_string()))
-			.map(|r| r.rows_affected)
+		events::record(&self.connection, &sql, bindings, |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await
+		.map(|r| r.rows_affected)
 	}
 
 	/// Run a truncate statement on the table. Returns the number of affected rows.
@@ -513,32 +1640,351 @@ impl Builder {
 	///
 	/// Returns an error if the query fails, or if a connection to the database cannot be established.
 	pub async fn truncate(self) -> Result<u64, Error> {
-		let mut conn = connection::get().await?;
-		let sql = format!("TRUNCATE TABLE {}", self.table);
+		let mut conn = self.write_connection().await?;
+		let sql = connection::grammar(&self.connection).compile_truncate(&self.table);
+
+		tracing::debug!(sql = sql.as_str(), "Executing TRUNCATE SQL query");
+
+		events::record(&self.connection, &sql, vec![], |bindings| {
+			conn.exec(&sql, bindings)
+		})
+		.await
+		.map(|r| r.rows_affected)
+	}
+}
+
+impl Builder {
+	/// Qualify the given column with the table (or the alias of the subquery) the query selects from.
+	fn qualify(&self, column: &str) -> String {
+		let table = self
+			.from_sub
+			.as_ref()
+			.map_or(&self.table, |(_, alias)| alias);
+
+		format!("{table}.{column}")
+	}
+
+	/// Fetch the results in chunks of the given size, using a limit and offset.
+	fn chunks<M: Model>(
+		mut self,
+		size: u64,
+	) -> Result<impl Stream<Item = Result<Vec<M>, Error>> + Send, Error> {
+		let size = chunk_size(size)?;
+
+		// without an order, the database may return the rows in a different order for each chunk
+		if self.order.is_empty() {
+			let primary_key = self.qualify(M::PRIMARY_KEY);
+			self = self.order_by(&primary_key, Direction::Ascending);
+		}
+		self.limit = Some(size);
+
+		Ok(stream::try_unfold(
+			Some((self, 0)),
+			move |state| async move {
+				let Some((mut query, offset)) = state else {
+					return Ok(None);
+				};
+
+				query.offset = Some(offset);
+				let models = query.fetch::<M>().await?;
+				if models.is_empty() {
+					return Ok(None);
+				}
+
+				// a partial chunk means there are no more rows, so there's no need to query for them
+				let next = (models.len() == size).then_some((query, offset + size));
+
+				Ok(Some((models, next)))
+			},
+		))
+	}
+
+	/// Fetch the results in chunks of the given size, using the last primary key of each chunk to fetch the next one.
+	fn chunks_by_id<M: Model>(
+		mut self,
+		size: u64,
+	) -> Result<impl Stream<Item = Result<Vec<M>, Error>> + Send, Error> {
+		let size = chunk_size(size)?;
+
+		// the existing conditions are grouped, so the key condition applies to all of them (even the ones joined with OR)
+		let conditions = std::mem::take(&mut self.r#where);
+		if !conditions.is_empty() {
+			self.r#where
+				.push(WhereClause::Group(conditions, Boolean::And));
+		}
+
+		// columns that aren't selected are filled with their defaults, so the key has to be selected for the next chunk to start after it
+		let key = self.qualify(M::PRIMARY_KEY);
+		let all = self.qualify("*");
+		let selects_key = self.columns.iter().any(|selection| {
+			matches!(selection, Selection::Column(column) if [M::PRIMARY_KEY, "*", &key, &all].contains(&column.as_str()))
+		});
+		if !self.columns.is_empty() && !selects_key {
+			self.columns.push(Selection::Column(key.clone()));
+		}
+
+		// the key is qualified with the table, so it isn't ambiguous when the query has joins
+		self.order = vec![Order {
+			column: key,
+			direction: Direction::Ascending,
+		}];
+		self.limit = Some(size);
+		self.offset = None;
+
+		Ok(stream::try_unfold(
+			Some((self, None)),
+			move |state| async move {
+				let Some((mut query, last_key)) = state else {
+					return Ok(None);
+				};
+
+				let after_last = last_key.is_some();
+				if let Some(key) = last_key {
+					query.r#where.push(WhereClause::Simple(Where {
+						column: query.qualify(M::PRIMARY_KEY),
+						boolean: Boolean::And,
+						operator: Operator::GreaterThan,
+						value: Some(key),
+					}));
+				}
+
+				let models = query.fetch::<M>().await?;
+				if after_last {
+					query.r#where.pop();
+				}
+
+				let Some(last) = models.last() else {
+					return Ok(None);
+				};
+
+				let next = if models.len() == size {
+					Some((query, Some(value::for_db(last.primary_key())?)))
+				} else {
+					None
+				};
+
+				Ok(Some((models, next)))
+			},
+		))
+	}
+
+	/// Limit the query to the results on the given page.
+	fn for_page(self, page: u64, per_page: u64) -> Result<Self, Error> {
+		if per_page == 0 {
+			return Err(Error::InvalidQuery);
+		}
+
+		let offset = usize::try_from((page - 1).saturating_mul(per_page))
+			.map_err(|_| Error::InvalidQuery)?;
+		let limit = usize::try_from(per_page).map_err(|_| Error::InvalidQuery)?;
+
+		Ok(self.offset(offset).limit(limit))
+	}
+
+	/// Resolve the query for a relationship of the model the query was started from.
+	fn relation_query(&self, relation: &str) -> Self {
+		let resolve = self
+			.relations
+			.expect("Relationship queries can only be used on queries started from a model.");
+
+		resolve(relation)
+	}
+
+	fn add_relation_function(self, relation: &str, function: &'static str, column: &str) -> Self {
+		let alias = format!("{relation}_{}_{column}", function.to_lowercase()).replace('.', "_");
+
+		self.add_relation_aggregate(
+			relation,
+			RelationAggregate::Function(function, column.to_string()),
+			alias,
+		)
+	}
+
+	fn add_relation_aggregate(
+		mut self,
+		relation: &str,
+		aggregate: RelationAggregate,
+		alias: String,
+	) -> Self {
+		let query = Box::new(self.relation_query(relation));
+
+		// keep selecting every column of the model alongside the aggregate
+		if self.columns.is_empty() {
+			self.columns
+				.push(Selection::Column(format!("{}.*", self.table)));
+		}
+
+		self.columns.push(Selection::Aggregate {
+			query,
+			aggregate,
+			alias,
+		});
+
+		self
+	}
+
+	/// Add a clause comparing the number of models in the given relationship, using `EXISTS` when only their existence matters.
+	fn add_has(
+		mut self,
+		relation: &str,
+		operator: Operator,
+		count: u64,
+		r#fn: impl FnOnce(Self) -> Self,
+	) -> Self {
+		let query = Box::new(r#fn(self.relation_query(relation)));
+
+		self.r#where.push(match (operator, count) {
+			(Operator::GreaterOrEqual, 1) | (Operator::GreaterThan, 0) => WhereClause::Exists {
+				query,
+				negated: false,
+				boolean: Boolean::And,
+			},
+			(Operator::LessThan, 1) | (Operator::Equals, 0) => WhereClause::Exists {
+				query,
+				negated: true,
+				boolean: Boolean::And,
+			},
+			(operator, count) => WhereClause::Count {
+				query,
+				operator,
+				count: Value::U64(count),
+				boolean: Boolean::And,
+			},
+		});
+
+		self
+	}
+
+	fn add_exists(mut self, negated: bool, r#fn: impl FnOnce(Self) -> Self) -> Self {
+		self.r#where.push(WhereClause::Exists {
+			negated,
+			boolean: Boolean::And,
+			query: Box::new(r#fn(Self::new(String::new()))),
+		});
+
+		self
+	}
+
+	fn add_join(mut self, r#type: JoinType, table: &str, clause: JoinClause) -> Self {
+		self.join.push(Join {
+			r#type,
+			table: table.to_string(),
+			conditions: clause.conditions,
+		});
+
+		self
+	}
+
+	async fn write_connection(&self) -> Result<connection::ConnectionGuard, Error> {
+		let conn = connection::get(&self.connection).await?;
+		connection::record_write(&self.connection);
+
+		Ok(conn)
+	}
+
+	async fn read_connection(&self) -> Result<connection::ConnectionGuard, Error> {
+		if self.use_write_connection {
+			return Ok(connection::get(&self.connection).await?);
+		}
+
+		Ok(connection::get_read(&self.connection).await?)
+	}
+
+	async fn aggregate<T: DeserializeOwned>(
+		self,
+		function: &str,
+		column: &str,
+	) -> Result<Option<T>, Error> {
+		let values = retry::for_reads(&self.connection)
+			.run(function, || async {
+				let mut conn = self.read_connection().await?;
+				let grammar = connection::grammar(&self.connection);
+				let sql = grammar.compile_aggregate(&self, function, &grammar.wrap(column));
+
+				events::record(
+					&self.connection,
+					&sql,
+					self.aggregate_bindings(),
+					|bindings| conn.get_values(&sql, bindings),
+				)
+				.await
+			})
+			.await?;
 
-		tracing::debug!(sql = sql.as_str(), "Executing TRUNCATE SQL query");
+		Ok(value::from_db(aggregate_value(values))?)
+	}
+
+	async fn select_values(&self) -> Result<Vec<Value>, Error> {
+		retry::for_reads(&self.connection)
+			.run("select", || async {
+				let mut conn = self.read_connection().await?;
+				let (sql, bindings) = (
+					self.to_sql(Type::Select, connection::grammar(&self.connection)),
+					self.get_bindings(),
+				);
+
+				tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing SELECT SQL query");
 
-		conn.exec(&sql, vec![])
+				events::record(&self.connection, &sql, bindings, |bindings| {
+					conn.get_values(&sql, bindings)
+				})
+				.await
+			})
 			.await
-			.map_err(|e| Error::Database(e.to_string()))
-			.map(|r| r.rows_affected)
 	}
 }
 
-impl Builder {
-	async fn _get(&self) -> Result<Vec<Value>, Error> {
-		let mut conn = connection::get().await?;
-		let (sql, bindings) = (self.to_sql(Type::Select), self.get_bindings());
+/// Pass each chunk from the stream to the callback, until it returns `false` or the stream ends.
+async fn each_chunk<M, F, Fut>(
+	chunks: impl Stream<Item = Result<Vec<M>, Error>>,
+	mut callback: F,
+) -> Result<bool, Error>
+where
+	F: FnMut(Vec<M>) -> Fut,
+	Fut: Future<Output = Result<bool, Error>>,
+{
+	let mut chunks = pin!(chunks);
+
+	while let Some(models) = chunks.try_next().await? {
+		if !callback(models).await? {
+			return Ok(false);
+		}
+	}
 
-		tracing::debug!(sql = sql.as_str(), bindings = ?bindings, "Executing SELECT SQL query");
+	Ok(true)
+}
 
-		let values = conn
-			.get_values(&sql, bindings)
-			.await
-			.map_err(|s| Error::Database(s.to_string()))?;
+/// Checks the size of the chunks results are fetched in.
+fn chunk_size(size: u64) -> Result<usize, Error> {
+	usize::try_from(size)
+		.ok()
+		.filter(|size| *size > 0)
+		.ok_or(Error::InvalidQuery)
+}
 
-		Ok(values)
+/// Takes the value of an aggregate query (the first column of the first row), which is `NULL` if there were no rows.
+fn aggregate_value(values: Vec<Value>) -> Value {
+	values
+		.into_iter()
+		.next()
+		.and_then(|row| match row {
+			Value::Map(map) => map.into_iter().next().map(|(_, value)| value),
+			_ => None,
+		})
+		.unwrap_or(Value::Null)
+}
+
+/// Overwrites the given default values with the columns present in the row.
+fn fill_defaults(defaults: Value, row: Value) -> Value {
+	let (Value::Map(mut defaults), Value::Map(row)) = (defaults, row) else {
+		unreachable!("models and rows are always maps")
+	};
+
+	for (column, value) in row {
+		defaults.insert(column, value);
 	}
+
+	Value::Map(defaults)
 }
 
 pub enum EagerLoad {
@@ -570,30 +2016,13 @@ impl From<Vec<&str>> for EagerLoad {
 
 pub struct Columns(Vec<(String, Value)>);
 
-impl Columns {
-	fn escape(column: &str) -> String {
-		let parts = column.split('.');
-
-		match connection::which_db() {
-			Database::MySQL => parts
-				.map(|part| format!("`{part}`"))
-				.collect::<Vec<String>>()
-				.join("."),
-			Database::PostgreSQL => parts
-				.map(|part| format!("\"{part}\""))
-				.collect::<Vec<String>>()
-				.join("."),
-		}
-	}
-}
-
 #[allow(clippy::fallible_impl_from)]
 impl From<Value> for Columns {
 	fn from(value: Value) -> Self {
 		match value {
 			Value::Map(map) => Self(
 				map.into_iter()
-					.map(|(column, value)| (Self::escape(&column.into_string().unwrap()), value))
+					.map(|(column, value)| (column.into_string().unwrap(), value))
 					.collect(),
 			),
 			_ => panic!("The provided value is not a map."),
@@ -606,7 +2035,7 @@ impl<T: Serialize> From<Vec<(&str, T)>> for Columns {
 		Self(
 			values
 				.iter()
-				.map(|(column, value)| (Self::escape(column), value::for_db(value).unwrap()))
+				.map(|(column, value)| ((*column).to_string(), value::for_db(value).unwrap()))
 				.collect(),
 		)
 	}
@@ -616,19 +2045,30 @@ impl<T: Serialize> From<&[(&str, T)]> for Columns {
 		Self(
 			values
 				.iter()
-				.map(|(column, value)| (Self::escape(column), value::for_db(value).unwrap()))
+				.map(|(column, value)| ((*column).to_string(), value::for_db(value).unwrap()))
 				.collect(),
 		)
 	}
 }
 
 /// Available sort directions.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
 	Ascending,
 	Descending,
 }
 
+impl Direction {
+	/// The opposite direction.
+	#[must_use]
+	pub const fn reverse(self) -> Self {
+		match self {
+			Self::Ascending => Self::Descending,
+			Self::Descending => Self::Ascending,
+		}
+	}
+}
+
 impl Display for Direction {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
@@ -656,6 +2096,56 @@ impl From<&str> for Direction {
 	}
 }
 
+/// Build the conditions matching the rows after the one the cursor points from (or before it, for cursors pointing to previous items),
+/// like `a > ? OR (a = ? AND (b < ? OR (b = ? AND id > ?)))`.
+fn keyset_clause(orders: &[Order], cursor: &Cursor) -> Result<WhereClause, Error> {
+	let Some((order, rest)) = orders.split_first() else {
+		return Err(Error::InvalidQuery);
+	};
+
+	let value = cursor
+		.parameter(column_name(&order.column))
+		.ok_or(Error::InvalidCursor)?
+		.clone();
+
+	let operator = if (order.direction == Direction::Ascending) == cursor.points_to_next_items() {
+		Operator::GreaterThan
+	} else {
+		Operator::LessThan
+	};
+
+	let comparison = WhereClause::Simple(Where {
+		operator,
+		column: order.column.clone(),
+		boolean: Boolean::And,
+		value: Some(value.clone()),
+	});
+
+	if rest.is_empty() {
+		return Ok(comparison);
+	}
+
+	let tie = WhereClause::Group(
+		vec![
+			WhereClause::Simple(Where {
+				column: order.column.clone(),
+				boolean: Boolean::And,
+				operator: Operator::Equals,
+				value: Some(value),
+			}),
+			keyset_clause(rest, cursor)?,
+		],
+		Boolean::Or,
+	);
+
+	Ok(WhereClause::Group(vec![comparison, tie], Boolean::And))
+}
+
+/// The name of a column, without the table it belongs to.
+fn column_name(column: &str) -> &str {
+	column.rsplit('.').next().unwrap_or(column)
+}
+
 /// An order clause.
 #[derive(Debug)]
 struct Order {
@@ -668,12 +2158,21 @@ struct Order {
 enum JoinType {
 	/// The `INNER JOIN` type.
 	Inner,
+	/// The `LEFT JOIN` type.
+	Left,
+	/// The `RIGHT JOIN` type.
+	Right,
+	/// The `CROSS JOIN` type.
+	Cross,
 }
 
 impl Display for JoinType {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Self::Inner => write!(f, "INNER JOIN"),
+			Self::Left => write!(f, "LEFT JOIN"),
+			Self::Right => write!(f, "RIGHT JOIN"),
+			Self::Cross => write!(f, "CROSS JOIN"),
 		}
 	}
 }
@@ -686,32 +2185,195 @@ pub enum Type {
 	Delete,
 }
 
+/// A column (or raw expression) in the select clause.
+#[derive(Debug)]
+enum Selection {
+	Column(String),
+	Raw(String, Vec<Value>),
+	/// An aggregate of the models in a relationship, computed with a correlated subquery.
+	Aggregate {
+		query: Box<Builder>,
+		aggregate: RelationAggregate,
+		alias: String,
+	},
+}
+
+impl Selection {
+	fn get_bindings(&self) -> Vec<Value> {
+		match self {
+			Self::Column(_) => vec![],
+			Self::Raw(_, bindings) => bindings.clone(),
+			Self::Aggregate {
+				query, aggregate, ..
+			} => match aggregate {
+				RelationAggregate::Count => query.count_bindings(),
+				RelationAggregate::Exists => query.get_bindings(),
+				RelationAggregate::Function(..) => query.aggregate_bindings(),
+			},
+		}
+	}
+}
+
+#[derive(Debug)]
+enum RelationAggregate {
+	Count,
+	Exists,
+	/// An aggregate function (like `SUM`) over the given column.
+	Function(&'static str, String),
+}
+
+impl RelationAggregate {
+	fn compile<G: Grammar + ?Sized>(&self, query: &Builder, grammar: &G) -> String {
+		match self {
+			Self::Count => grammar.compile_count(query),
+			Self::Exists => format!("EXISTS ({})", grammar.compile_select(query)),
+			Self::Function(function, column) => {
+				grammar.compile_aggregate(query, function, &grammar.wrap(column))
+			},
+		}
+	}
+}
+
+/// A common table expression, which can be referred to by its name from the rest of the query.
+#[derive(Debug)]
+struct Cte {
+	name: String,
+	query: Box<Builder>,
+	/// The query that builds on the rows of the expression, for recursive expressions.
+	recursive: Option<Box<Builder>>,
+}
+
+/// A query whose results are combined with the ones of the query it was added to.
+#[derive(Debug)]
+struct Union {
+	query: Box<Builder>,
+	all: bool,
+}
+
 /// A join clause.
 #[derive(Debug)]
 struct Join {
-	column: String,
-	first: String,
-	second: String,
+	table: String,
 	r#type: JoinType,
-	operator: Operator,
+	conditions: Vec<WhereClause>,
+}
+
+/// The conditions of a join, added in the closure passed to [`Builder::join_with`].
+#[derive(Debug, Default)]
+pub struct JoinClause {
+	conditions: Vec<WhereClause>,
+}
+
+impl JoinClause {
+	/// Join on the given columns, like `users.id = posts.user_id`.
+	#[must_use]
+	pub fn on<Op: Into<Operator>>(self, first: &str, op: Op, second: &str) -> Self {
+		self.add_on(Boolean::And, first, op, second)
+	}
+
+	/// Add another column condition to the join, which must also be met.
+	#[must_use]
+	pub fn and_on<Op: Into<Operator>>(self, first: &str, op: Op, second: &str) -> Self {
+		self.add_on(Boolean::And, first, op, second)
+	}
+
+	/// Add an alternative column condition to the join.
+	#[must_use]
+	pub fn or_on<Op: Into<Operator>>(self, first: &str, op: Op, second: &str) -> Self {
+		self.add_on(Boolean::Or, first, op, second)
+	}
+
+	/// Add a condition comparing a column to a value, which is bound as a parameter.
+	///
+	/// # Panics
+	///
+	/// Panics if the provided value cannot be serialized.
+	#[must_use]
+	pub fn r#where<T, Op>(mut self, column: &str, operator: Op, value: T) -> Self
+	where
+		Op: Into<Operator>,
+		T: serde::Serialize,
+	{
+		self.conditions.push(WhereClause::Simple(Where {
+			boolean: Boolean::And,
+			operator: operator.into(),
+			column: column.to_string(),
+			value: Some(value::for_db(value).unwrap()),
+		}));
+
+		self
+	}
+
+	fn add_on<Op: Into<Operator>>(
+		mut self,
+		boolean: Boolean,
+		first: &str,
+		op: Op,
+		second: &str,
+	) -> Self {
+		self.conditions.push(WhereClause::Column {
+			boolean,
+			operator: op.into(),
+			first: first.to_string(),
+			second: second.to_string(),
+		});
+
+		self
+	}
 }
 
 #[derive(Debug)]
 enum WhereClause {
 	Simple(Where),
-	Group(Vec<WhereClause>, Boolean),
+	Group(Vec<Self>, Boolean),
+	Raw {
+		sql: String,
+		bindings: Vec<Value>,
+		boolean: Boolean,
+	},
+	/// Compares two columns, like the conditions of a join.
+	Column {
+		first: String,
+		operator: Operator,
+		second: String,
+		boolean: Boolean,
+	},
+	/// Compares a column to the results of a subquery.
+	Sub {
+		column: String,
+		operator: Operator,
+		query: Box<Builder>,
+		boolean: Boolean,
+	},
+	Exists {
+		query: Box<Builder>,
+		negated: bool,
+		boolean: Boolean,
+	},
+	/// Compares the number of rows matched by a subquery to a value.
+	Count {
+		query: Box<Builder>,
+		operator: Operator,
+		count: Value,
+		boolean: Boolean,
+	},
 }
 
 impl WhereClause {
-	fn to_sql(&self, add_boolean: bool) -> String {
+	/// Compile a list of clauses, joined by their booleans.
+	fn compile_all<G: Grammar + ?Sized>(clauses: &[Self], grammar: &G) -> String {
+		clauses
+			.iter()
+			.enumerate()
+			.map(|(i, clause)| clause.to_sql(grammar, i != 0))
+			.collect()
+	}
+
+	fn to_sql<G: Grammar + ?Sized>(&self, grammar: &G, add_boolean: bool) -> String {
 		match self {
-			Self::Simple(where_clause) => where_clause.to_sql(add_boolean),
+			Self::Simple(where_clause) => where_clause.to_sql(grammar, add_boolean),
 			Self::Group(where_clauses, boolean) => {
-				let mut sql = String::new();
-
-				for (i, where_clause) in where_clauses.iter().enumerate() {
-					sql.push_str(&where_clause.to_sql(i != 0));
-				}
+				let sql = Self::compile_all(where_clauses, grammar);
 
 				if add_boolean {
 					format!(" {boolean} ({sql})")
@@ -719,6 +2381,58 @@ impl WhereClause {
 					format!("({sql})")
 				}
 			},
+			Self::Raw { sql, boolean, .. } => with_boolean(sql.clone(), *boolean, add_boolean),
+			Self::Column {
+				first,
+				operator,
+				second,
+				boolean,
+			} => with_boolean(
+				format!(
+					"{} {operator} {}",
+					grammar.wrap(first),
+					grammar.wrap(second)
+				),
+				*boolean,
+				add_boolean,
+			),
+			Self::Sub {
+				column,
+				operator,
+				query,
+				boolean,
+			} => with_boolean(
+				format!(
+					"{} {operator} ({})",
+					grammar.wrap(column),
+					grammar.compile_select(query)
+				),
+				*boolean,
+				add_boolean,
+			),
+			Self::Exists {
+				query,
+				negated,
+				boolean,
+			} => with_boolean(
+				format!(
+					"{}EXISTS ({})",
+					if *negated { "NOT " } else { "" },
+					grammar.compile_select(query)
+				),
+				*boolean,
+				add_boolean,
+			),
+			Self::Count {
+				query,
+				operator,
+				boolean,
+				..
+			} => with_boolean(
+				format!("({}) {operator} ?", grammar.compile_count(query)),
+				*boolean,
+				add_boolean,
+			),
 		}
 	}
 
@@ -736,10 +2450,27 @@ impl WhereClause {
 			Self::Group(where_clauses, _) => {
 				where_clauses.iter().flat_map(Self::get_bindings).collect()
 			},
+			Self::Raw { bindings, .. } => bindings.clone(),
+			Self::Column { .. } => vec![],
+			Self::Sub { query, .. } | Self::Exists { query, .. } => query.get_bindings(),
+			Self::Count { query, count, .. } => {
+				let mut bindings = query.count_bindings();
+				bindings.push(count.clone());
+				bindings
+			},
 		}
 	}
 }
 
+/// Prefixes the compiled clause with its boolean, unless it's the first one.
+fn with_boolean(sql: String, boolean: Boolean, add_boolean: bool) -> String {
+	if add_boolean {
+		format!(" {boolean} {sql} ")
+	} else {
+		sql
+	}
+}
+
 /// A where clause.
 #[derive(Debug)]
 struct Where {
@@ -750,10 +2481,10 @@ struct Where {
 }
 
 impl Where {
-	fn to_sql(&self, add_boolean: bool) -> String {
+	fn to_sql<G: Grammar + ?Sized>(&self, grammar: &G, add_boolean: bool) -> String {
 		let sql = format!(
 			"{} {} {}",
-			self.column,
+			grammar.wrap(&self.column),
 			self.operator,
 			self.value.as_ref().map_or_else(String::new, |value| {
 				value.as_array().map_or_else(
@@ -886,37 +2617,12 @@ impl AsRef<Self> for Builder {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::grammar::{MySQL, Postgres, SQLite};
 
-	#[cfg(any(
-		all(feature = "mysql", not(feature = "postgres")),
-		all(feature = "postgres", not(feature = "mysql"))
-	))]
 	fn normalize_sql(sql: &str) -> String {
 		sql.split_whitespace().collect::<Vec<_>>().join(" ")
 	}
 
-	#[cfg(all(feature = "mysql", not(feature = "postgres")))]
-	fn quote_identifier(part: &str) -> String {
-		format!("`{part}`")
-	}
-
-	#[cfg(all(feature = "postgres", not(feature = "mysql")))]
-	fn quote_identifier(part: &str) -> String {
-		format!("\"{part}\"")
-	}
-
-	#[cfg(any(
-		all(feature = "mysql", not(feature = "postgres")),
-		all(feature = "postgres", not(feature = "mysql"))
-	))]
-	fn quote_column(column: &str) -> String {
-		column
-			.split('.')
-			.map(quote_identifier)
-			.collect::<Vec<_>>()
-			.join(".")
-	}
-
 	#[test]
 	fn parses_direction_aliases() {
 		assert!(matches!(Direction::from("asc"), Direction::Ascending));
@@ -952,10 +2658,6 @@ mod tests {
 		assert!(matches!(err, Error::InvalidQuery));
 	}
 
-	#[cfg(any(
-		all(feature = "mysql", not(feature = "postgres")),
-		all(feature = "postgres", not(feature = "mysql"))
-	))]
 	#[test]
 	fn builds_expected_select_sql_and_binding_order() {
 		let query = Builder::new("users".to_string())
@@ -971,21 +2673,17 @@ mod tests {
 			.limit(10)
 			.offset(20);
 
-		let expected_sql = format!(
-			"SELECT * FROM users INNER JOIN {} ON users.id = profiles.user_id \
-			 WHERE {} = ? AND ({} = ? OR {} = ? ) AND {} IN (?, ?) \
-			 ORDER BY {} DESC LIMIT 10 OFFSET 20",
-			quote_identifier("profiles"),
-			quote_column("users.active"),
-			quote_column("users.name"),
-			quote_column("users.role"),
-			quote_column("users.id"),
-			quote_column("users.name")
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"SELECT * FROM users INNER JOIN `profiles` ON `users`.`id` = `profiles`.`user_id` \
+			 WHERE `users`.`active` = ? AND (`users`.`name` = ? OR `users`.`role` = ? ) \
+			 AND `users`.`id` IN (?, ?) ORDER BY `users`.`name` DESC LIMIT 10 OFFSET 20"
 		);
-
 		assert_eq!(
-			normalize_sql(&query.to_sql(Type::Select)),
-			normalize_sql(&expected_sql)
+			normalize_sql(&query.to_sql(Type::Select, &Postgres)),
+			"SELECT * FROM users INNER JOIN \"profiles\" ON \"users\".\"id\" = \"profiles\".\"user_id\" \
+			 WHERE \"users\".\"active\" = ? AND (\"users\".\"name\" = ? OR \"users\".\"role\" = ? ) \
+			 AND \"users\".\"id\" IN (?, ?) ORDER BY \"users\".\"name\" DESC LIMIT 10 OFFSET 20"
 		);
 		assert_eq!(
 			query.get_bindings(),
@@ -999,10 +2697,333 @@ mod tests {
 		);
 	}
 
-	#[cfg(any(
-		all(feature = "mysql", not(feature = "postgres")),
-		all(feature = "postgres", not(feature = "mysql"))
-	))]
+	#[test]
+	fn builds_expected_sql_for_selected_columns() {
+		let query = Builder::new("users".to_string())
+			.select(&["users.id", "email as contact"])
+			.select_raw("LOWER(name) = ? AS named", vec![Value::from("alice")])
+			.distinct()
+			.r#where("active", "=", true);
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"SELECT DISTINCT `users`.`id`, `email` AS `contact`, LOWER(name) = ? AS named FROM users WHERE `active` = ?"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![Value::String("alice".to_string()), Value::Bool(true)]
+		);
+
+		assert_eq!(
+			normalize_sql(
+				&Builder::new("users".to_string())
+					.select(&["email"])
+					.distinct()
+					.to_sql(Type::Count, &Postgres)
+			),
+			"SELECT COUNT(*) FROM (SELECT DISTINCT \"email\" FROM users) AS \"temp_table\""
+		);
+	}
+
+	#[test]
+	fn builds_expected_aggregate_sql() {
+		let query = Builder::new("orders".to_string())
+			.join("users", "users.id", "=", "orders.user_id")
+			.r#where("users.active", "=", true);
+
+		assert_eq!(
+			normalize_sql(&MySQL.compile_aggregate(&query, "SUM", &MySQL.wrap("orders.total"))),
+			"SELECT SUM(`orders`.`total`) AS aggregate FROM orders INNER JOIN `users` ON `users`.`id` = `orders`.`user_id` WHERE `users`.`active` = ?"
+		);
+	}
+
+	#[test]
+	fn builds_expected_grouped_sql() {
+		let query = Builder::new("orders".to_string())
+			.select(&["user_id"])
+			.select_raw("SUM(total) AS total", vec![])
+			.r#where("status", "=", "paid")
+			.group_by(&["user_id"])
+			.having("total", ">", 100)
+			.having_raw("COUNT(*) > ?", vec![Value::I64(2)])
+			.order_by("total", "desc");
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"SELECT `user_id`, SUM(total) AS total FROM orders WHERE `status` = ? GROUP BY `user_id` \
+			 HAVING `total` > ? AND COUNT(*) > ? ORDER BY `total` DESC"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![
+				Value::String("paid".to_string()),
+				Value::I32(100),
+				Value::I64(2)
+			]
+		);
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Count, &MySQL)),
+			"SELECT COUNT(*) FROM (SELECT `user_id`, SUM(total) AS total FROM orders WHERE `status` = ? GROUP BY `user_id` \
+			 HAVING `total` > ? AND COUNT(*) > ? ORDER BY `total` DESC) AS `temp_table`"
+		);
+		assert_eq!(query.count_bindings(), query.get_bindings());
+	}
+
+	#[test]
+	fn builds_right_joins_with_conditions() {
+		let query = Builder::new("users".to_string()).right_join_with("teams", |join| {
+			join.on("users.team_id", "=", "teams.id")
+				.r#where("teams.active", "=", true)
+		});
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"SELECT * FROM users RIGHT JOIN `teams` ON `users`.`team_id` = `teams`.`id` AND `teams`.`active` = ?"
+		);
+		assert_eq!(query.get_bindings(), vec![Value::Bool(true)]);
+	}
+
+	#[test]
+	fn wraps_join_columns_named_after_reserved_words() {
+		let query = Builder::new("orders".to_string()).join_with("groups", |join| {
+			join.on("orders.group", "=", "groups.id")
+				.or_on("orders.user", "=", "groups.user")
+		});
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &Postgres)),
+			"SELECT * FROM orders INNER JOIN \"groups\" ON \"orders\".\"group\" = \"groups\".\"id\" \
+			 OR \"orders\".\"user\" = \"groups\".\"user\""
+		);
+	}
+
+	#[test]
+	fn builds_expected_join_sql_and_binding_order() {
+		let query = Builder::new("users".to_string())
+			.select_raw("? AS label", vec![Value::from("user")])
+			.join_with("contacts", |join| {
+				join.on("users.id", "=", "contacts.user_id")
+					.or_on("users.email", "=", "contacts.email")
+					.r#where("contacts.type", "=", "email")
+			})
+			.left_join("profiles", "users.id", "=", "profiles.user_id")
+			.right_join("teams", "users.team_id", "=", "teams.id")
+			.cross_join("regions")
+			.r#where("users.active", "=", true);
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &Postgres)),
+			"SELECT ? AS label FROM users \
+			 INNER JOIN \"contacts\" ON \"users\".\"id\" = \"contacts\".\"user_id\" OR \"users\".\"email\" = \"contacts\".\"email\" AND \"contacts\".\"type\" = ? \
+			 LEFT JOIN \"profiles\" ON \"users\".\"id\" = \"profiles\".\"user_id\" \
+			 RIGHT JOIN \"teams\" ON \"users\".\"team_id\" = \"teams\".\"id\" \
+			 CROSS JOIN \"regions\" WHERE \"users\".\"active\" = ?"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![
+				Value::String("user".to_string()),
+				Value::String("email".to_string()),
+				Value::Bool(true),
+			]
+		);
+	}
+
+	#[test]
+	fn builds_expected_subquery_sql_and_binding_order() {
+		let query = Builder::new(String::new())
+			.from_sub(
+				Builder::new("orders".to_string())
+					.select(&["user_id"])
+					.select_raw("SUM(total) AS spent", vec![])
+					.r#where("status", "=", "paid")
+					.group_by(&["user_id"]),
+				"totals",
+			)
+			.where_in_sub("user_id", |query| {
+				query
+					.from("users")
+					.select(&["id"])
+					.r#where("active", "=", true)
+			})
+			.where_not_exists(|query| {
+				query
+					.from("bans")
+					.select_raw("1", vec![])
+					.r#where("reason", "=", "fraud")
+			})
+			.where_sub("spent", ">", |query| {
+				query.from("orders").select_raw("AVG(total)", vec![])
+			})
+			.r#where("spent", ">", 10);
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"SELECT * FROM (SELECT `user_id`, SUM(total) AS spent FROM orders WHERE `status` = ? GROUP BY `user_id`) AS `totals` \
+			 WHERE `user_id` IN (SELECT `id` FROM users WHERE `active` = ?) \
+			 AND NOT EXISTS (SELECT 1 FROM bans WHERE `reason` = ?) \
+			 AND `spent` > (SELECT AVG(total) FROM orders) AND `spent` > ?"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![
+				Value::String("paid".to_string()),
+				Value::Bool(true),
+				Value::String("fraud".to_string()),
+				Value::I32(10),
+			]
+		);
+	}
+
+	#[test]
+	fn builds_expected_union_sql_and_binding_order() {
+		let query = Builder::new("posts".to_string())
+			.select(&["id", "created_at"])
+			.r#where("published", "=", true)
+			.union(
+				Builder::new("comments".to_string())
+					.select(&["id", "created_at"])
+					.r#where("approved", "=", false),
+			)
+			.union_all(
+				Builder::new("likes".to_string())
+					.select(&["id", "created_at"])
+					.r#where("user_id", "=", 2),
+			)
+			.order_by("created_at", "desc")
+			.limit(10);
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &MySQL)),
+			"(SELECT `id`, `created_at` FROM posts WHERE `published` = ?) \
+			 UNION (SELECT `id`, `created_at` FROM comments WHERE `approved` = ?) \
+			 UNION ALL (SELECT `id`, `created_at` FROM likes WHERE `user_id` = ?) \
+			 ORDER BY `created_at` DESC LIMIT 10"
+		);
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &SQLite)),
+			"SELECT * FROM (SELECT \"id\", \"created_at\" FROM posts WHERE \"published\" = ?) \
+			 UNION SELECT * FROM (SELECT \"id\", \"created_at\" FROM comments WHERE \"approved\" = ?) \
+			 UNION ALL SELECT * FROM (SELECT \"id\", \"created_at\" FROM likes WHERE \"user_id\" = ?) \
+			 ORDER BY \"created_at\" DESC LIMIT 10"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![Value::Bool(true), Value::Bool(false), Value::I32(2)]
+		);
+		assert_eq!(
+			normalize_sql(&MySQL.compile_aggregate(&query, "MAX", "id")),
+			format!(
+				"SELECT MAX(id) AS aggregate FROM ({}) AS `temp_table`",
+				normalize_sql(&query.to_sql(Type::Select, &MySQL))
+			)
+		);
+		assert_eq!(query.aggregate_bindings(), query.get_bindings());
+	}
+
+	#[test]
+	fn builds_expected_cte_sql_and_binding_order() {
+		let query = Builder::new("tree".to_string())
+			.with_cte(
+				"roots",
+				Builder::new("categories".to_string()).where_null("parent_id"),
+			)
+			.with_recursive(
+				"tree",
+				Builder::new("categories".to_string()).r#where("id", "=", 1),
+				Builder::new("categories".to_string())
+					.select(&["categories.*"])
+					.join("tree", "categories.parent_id", "=", "tree.id")
+					.r#where("categories.hidden", "=", false),
+			)
+			.r#where("name", "like", "A%");
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &Postgres)),
+			"WITH RECURSIVE \"roots\" AS (SELECT * FROM categories WHERE \"parent_id\" IS NULL ), \
+			 \"tree\" AS (SELECT * FROM categories WHERE \"id\" = ? \
+			 UNION ALL SELECT \"categories\".* FROM categories INNER JOIN \"tree\" ON \"categories\".\"parent_id\" = \"tree\".\"id\" WHERE \"categories\".\"hidden\" = ?) \
+			 SELECT * FROM tree WHERE \"name\" LIKE ?"
+		);
+		assert!(normalize_sql(&query.to_sql(Type::Count, &Postgres))
+			.ends_with("SELECT COUNT(*) FROM tree WHERE \"name\" LIKE ?"));
+		assert_eq!(
+			query.get_bindings(),
+			vec![
+				Value::I32(1),
+				Value::Bool(false),
+				Value::String("A%".to_string()),
+			]
+		);
+		assert_eq!(query.aggregate_bindings(), query.get_bindings());
+	}
+
+	#[test]
+	fn builds_keyset_conditions_for_mixed_orders() {
+		let cursor = Cursor::from_row(
+			&rbs::to_value(serde_json::json!({ "views": 10, "title": "Hello", "id": 3 })).unwrap(),
+			["views", "title", "id"],
+			true,
+		)
+		.unwrap();
+
+		let mut query = Builder::new("posts".to_string())
+			.r#where("published", "=", true)
+			.order_by("posts.views", "desc")
+			.order_by("title", "asc")
+			.order_by("id", "asc");
+		query
+			.r#where
+			.push(keyset_clause(&query.order, &cursor).unwrap());
+
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Select, &Postgres)),
+			"SELECT * FROM posts WHERE \"published\" = ? AND (\"posts\".\"views\" < ? OR \
+			 (\"posts\".\"views\" = ? AND (\"title\" > ? OR (\"title\" = ? AND \"id\" > ? )))) \
+			 ORDER BY \"posts\".\"views\" DESC, \"title\" ASC, \"id\" ASC"
+		);
+		assert_eq!(
+			query.get_bindings(),
+			vec![
+				Value::Bool(true),
+				Value::U64(10),
+				Value::U64(10),
+				Value::String("Hello".to_string()),
+				Value::String("Hello".to_string()),
+				Value::U64(3),
+			]
+		);
+
+		let previous = Cursor::from_row(
+			&rbs::to_value(serde_json::json!({ "views": 10, "id": 3 })).unwrap(),
+			["views", "id"],
+			false,
+		)
+		.unwrap();
+		let query = Builder::new("posts".to_string())
+			.order_by("views", "desc")
+			.order_by("id", "asc");
+		let mut reversed = Builder::new("posts".to_string());
+		reversed
+			.r#where
+			.push(keyset_clause(&query.order, &previous).unwrap());
+
+		assert_eq!(
+			normalize_sql(&reversed.to_sql(Type::Select, &Postgres)),
+			"SELECT * FROM posts WHERE (\"views\" > ? OR (\"views\" = ? AND \"id\" < ? ))"
+		);
+		assert!(matches!(
+			keyset_clause(
+				&Builder::new("posts".to_string())
+					.order_by("rank", "asc")
+					.order,
+				&cursor
+			),
+			Err(Error::InvalidCursor)
+		));
+	}
+
 	#[test]
 	fn builds_expected_count_sql_for_null_checks() {
 		let query = Builder::new("users".to_string())
@@ -1010,16 +3031,13 @@ mod tests {
 			.where_not_null("users.email")
 			.where_null("users.deleted_at");
 
-		let expected_sql = format!(
-			"SELECT COUNT(*) FROM users WHERE {} = ? AND {} IS NOT NULL AND {} IS NULL",
-			quote_column("users.active"),
-			quote_column("users.email"),
-			quote_column("users.deleted_at")
+		assert_eq!(
+			normalize_sql(&query.to_sql(Type::Count, &MySQL)),
+			"SELECT COUNT(*) FROM users WHERE `users`.`active` = ? AND `users`.`email` IS NOT NULL AND `users`.`deleted_at` IS NULL"
 		);
-
 		assert_eq!(
-			normalize_sql(&query.to_sql(Type::Count)),
-			normalize_sql(&expected_sql)
+			normalize_sql(&query.to_sql(Type::Count, &Postgres)),
+			"SELECT COUNT(*) FROM users WHERE \"users\".\"active\" = ? AND \"users\".\"email\" IS NOT NULL AND \"users\".\"deleted_at\" IS NULL"
 		);
 		assert_eq!(query.get_bindings(), vec![Value::Bool(true)]);
 	}
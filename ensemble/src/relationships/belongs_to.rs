@@ -3,8 +3,8 @@ use rbs::Value;
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Debug};
 
-use super::{find_related, Relationship, Status};
-use crate::{query::Builder, value::serializing_for_db, Error, Model};
+use super::{existence_base, find_related, Relationship, Status};
+use crate::{lazy_loading, query::Builder, value::serializing_for_db, Error, Model};
 
 /// ## A Belongs To relationship.
 /// A belongs to relationship is used to define relationships where a model is the child to a single models. For example, a website may belong to a user.
@@ -35,6 +35,7 @@ use crate::{query::Builder, value::serializing_for_db, Error, Model};
 /// ```
 #[derive(Clone, Default)]
 pub struct BelongsTo<Local: Model, Related: Model> {
+	name: &'static str,
 	local_key: String,
 	relation: Status<Related>,
 	_local: std::marker::PhantomData<Local>,
@@ -47,10 +48,11 @@ impl<Local: Model, Related: Model> Relationship for BelongsTo<Local, Related> {
 	type Key = Related::PrimaryKey;
 	type RelatedKey = Option<String>;
 
-	fn build(value: Self::Key, local_key: Self::RelatedKey) -> Self {
+	fn build(name: &'static str, value: Self::Key, local_key: Self::RelatedKey) -> Self {
 		let local_key = local_key.unwrap_or_else(|| Related::PRIMARY_KEY.to_snake_case());
 
 		Self {
+			name,
 			value,
 			local_key,
 			relation: Status::initial(),
@@ -71,6 +73,13 @@ impl<Local: Model, Related: Model> Relationship for BelongsTo<Local, Related> {
 	/// Get the related model.
 	async fn get(&mut self) -> Result<&mut Self::Value, Error> {
 		if self.relation.is_none() {
+			// the relationship was eager loaded, but there was no related model
+			if self.relation.is_loaded() {
+				return Err(Error::NotFound);
+			}
+
+			lazy_loading::check(Local::NAME, self.name)?;
+
 			let relation = self.query().first().await?.ok_or(Error::NotFound)?;
 
 			self.relation = Status::Fetched(Some(relation));
@@ -97,6 +106,16 @@ impl<Local: Model, Related: Model> Relationship for BelongsTo<Local, Related> {
 			.limit(1)
 	}
 
+	fn existence_query(&self, local_column: &str) -> Builder {
+		let (query, table) = existence_base::<Local, Related>();
+
+		query.where_column(
+			&format!("{table}.{}", self.local_key),
+			"=",
+			&format!("{}.{}", Local::TABLE_NAME, local_column),
+		)
+	}
+
 	fn r#match(&mut self, related: &[HashMap<String, Value>]) -> Result<(), Error> {
 		let related = find_related(related, &self.local_key, &self.value, true)?;
 
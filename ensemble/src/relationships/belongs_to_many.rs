@@ -3,8 +3,8 @@ use rbs::Value;
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Debug};
 
-use super::{find_related, Relationship, Status};
-use crate::{query::Builder, value::serializing_for_db, Error, Model};
+use super::{existence_base, find_related, Relationship, Status};
+use crate::{lazy_loading, query::Builder, value::serializing_for_db, Error, Model};
 
 /// ## A Many to Many relationship.
 /// A many to many relationship is used to define relationships where a model is the parent of one or more child models, but can also be a child to multiple parent models.
@@ -34,6 +34,7 @@ use crate::{query::Builder, value::serializing_for_db, Error, Model};
 /// ```
 #[derive(Clone, Default)]
 pub struct BelongsToMany<Local: Model, Related: Model> {
+	name: &'static str,
 	local_key: String,
 	foreign_key: String,
 	pivot_table: String,
@@ -48,7 +49,11 @@ impl<Local: Model, Related: Model> Relationship for BelongsToMany<Local, Related
 	type Key = Related::PrimaryKey;
 	type RelatedKey = (Option<String>, Option<String>, Option<String>);
 
-	fn build(value: Self::Key, (pivot_table, foreign_key, local_key): Self::RelatedKey) -> Self {
+	fn build(
+		name: &'static str,
+		value: Self::Key,
+		(pivot_table, foreign_key, local_key): Self::RelatedKey,
+	) -> Self {
 		let pivot_table = pivot_table.unwrap_or_else(|| {
 			let mut names = [Local::NAME.to_string(), Related::NAME.to_string()];
 			names.sort();
@@ -64,6 +69,7 @@ impl<Local: Model, Related: Model> Relationship for BelongsToMany<Local, Related
 		});
 
 		Self {
+			name,
 			value,
 			local_key,
 			foreign_key,
@@ -95,6 +101,8 @@ impl<Local: Model, Related: Model> Relationship for BelongsToMany<Local, Related
 
 	async fn get(&mut self) -> Result<&mut Self::Value, Error> {
 		if self.relation.is_none() {
+			lazy_loading::check(Local::NAME, self.name)?;
+
 			let relation = self.query().get().await?;
 
 			self.relation = Status::Fetched(Some(relation));
@@ -123,12 +131,27 @@ impl<Local: Model, Related: Model> Relationship for BelongsToMany<Local, Related
 			)
 	}
 
+	fn existence_query(&self, local_column: &str) -> Builder {
+		let (query, table) = existence_base::<Local, Related>();
+
+		query
+			.join(
+				&self.pivot_table,
+				&format!("{table}.{}", Related::PRIMARY_KEY),
+				"=",
+				&format!("{}.{}", self.pivot_table, self.foreign_key),
+			)
+			.where_column(
+				&format!("{}.{}", self.pivot_table, self.local_key),
+				"=",
+				&format!("{}.{}", Local::TABLE_NAME, local_column),
+			)
+	}
+
 	fn r#match(&mut self, related: &[HashMap<String, Value>]) -> Result<(), Error> {
 		let related = find_related(related, &self.foreign_key, &self.value, false)?;
 
-		if !related.is_empty() {
-			self.relation = Status::Fetched(Some(related));
-		}
+		self.relation = Status::Fetched(Some(related));
 
 		Ok(())
 	}
@@ -3,8 +3,9 @@ use rbs::Value;
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Debug};
 
-use super::{find_related, Relationship, Status};
+use super::{existence_base, find_related, Relationship, Status};
 use crate::{
+	lazy_loading,
 	query::Builder,
 	value::{self, serializing_for_db},
 	Error, Model,
@@ -41,6 +42,7 @@ use crate::{
 /// ```
 #[derive(Clone, Default)]
 pub struct HasMany<Local: Model, Related: Model> {
+	name: &'static str,
 	foreign_key: String,
 	relation: Status<Vec<Related>>,
 	/// The value of the local model's primary key.
@@ -52,12 +54,13 @@ impl<Local: Model, Related: Model> Relationship for HasMany<Local, Related> {
 	type Key = Local::PrimaryKey;
 	type RelatedKey = Option<String>;
 
-	fn build(value: Self::Key, foreign_key: Self::RelatedKey) -> Self {
+	fn build(name: &'static str, value: Self::Key, foreign_key: Self::RelatedKey) -> Self {
 		let foreign_key = foreign_key.unwrap_or_else(|| {
 			format!("{}_{}", Local::NAME.to_snake_case(), Local::PRIMARY_KEY).to_snake_case()
 		});
 
 		Self {
+			name,
 			value,
 			foreign_key,
 			relation: Status::initial(),
@@ -85,6 +88,8 @@ impl<Local: Model, Related: Model> Relationship for HasMany<Local, Related> {
 	/// Returns an error if the model cannot be retrieved, or if a connection to the database cannot be established.
 	async fn get(&mut self) -> Result<&mut Self::Value, Error> {
 		if self.relation.is_none() {
+			lazy_loading::check(Local::NAME, self.name)?;
+
 			let relation = self.query().get().await?;
 
 			self.relation = Status::Fetched(Some(relation));
@@ -107,12 +112,20 @@ impl<Local: Model, Related: Model> Relationship for HasMany<Local, Related> {
 			.where_not_null(&format!("{}.{}", Related::TABLE_NAME, self.foreign_key))
 	}
 
+	fn existence_query(&self, local_column: &str) -> Builder {
+		let (query, table) = existence_base::<Local, Related>();
+
+		query.where_column(
+			&format!("{table}.{}", self.foreign_key),
+			"=",
+			&format!("{}.{}", Local::TABLE_NAME, local_column),
+		)
+	}
+
 	fn r#match(&mut self, related: &[HashMap<String, Value>]) -> Result<(), Error> {
 		let related = find_related(related, &self.foreign_key, &self.value, false)?;
 
-		if !related.is_empty() {
-			self.relation = Status::Fetched(Some(related));
-		}
+		self.relation = Status::Fetched(Some(related));
 
 		Ok(())
 	}
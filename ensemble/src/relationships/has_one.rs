@@ -3,8 +3,8 @@ use rbs::Value;
 use serde::Serialize;
 use std::{collections::HashMap, fmt::Debug};
 
-use super::{find_related, Relationship, Status};
-use crate::{query::Builder, value::serializing_for_db, Error, Model};
+use super::{existence_base, find_related, Relationship, Status};
+use crate::{lazy_loading, query::Builder, value::serializing_for_db, Error, Model};
 
 /// ## A One to One relationship.
 /// A one-to-one relationship is a very basic type of database relationship. For example, a User model might be associated with one Phone model.
@@ -35,6 +35,7 @@ use crate::{query::Builder, value::serializing_for_db, Error, Model};
 /// ```
 #[derive(Clone, Default)]
 pub struct HasOne<Local: Model, Related: Model> {
+	name: &'static str,
 	foreign_key: String,
 	relation: Status<Related>,
 	/// The value of the local model's primary key.
@@ -46,12 +47,13 @@ impl<Local: Model, Related: Model> Relationship for HasOne<Local, Related> {
 	type Key = Local::PrimaryKey;
 	type RelatedKey = Option<String>;
 
-	fn build(value: Self::Key, foreign_key: Self::RelatedKey) -> Self {
+	fn build(name: &'static str, value: Self::Key, foreign_key: Self::RelatedKey) -> Self {
 		let foreign_key = foreign_key.unwrap_or_else(|| {
 			format!("{}_{}", Local::NAME.to_snake_case(), Local::PRIMARY_KEY).to_snake_case()
 		});
 
 		Self {
+			name,
 			value,
 			foreign_key,
 			relation: Status::initial(),
@@ -71,6 +73,13 @@ impl<Local: Model, Related: Model> Relationship for HasOne<Local, Related> {
 
 	async fn get(&mut self) -> Result<&mut Self::Value, Error> {
 		if self.relation.is_none() {
+			// the relationship was eager loaded, but there was no related model
+			if self.relation.is_loaded() {
+				return Err(Error::NotFound);
+			}
+
+			lazy_loading::check(Local::NAME, self.name)?;
+
 			let relation = self.query().first().await?.ok_or(Error::NotFound)?;
 
 			self.relation = Status::Fetched(Some(relation));
@@ -98,6 +107,16 @@ impl<Local: Model, Related: Model> Relationship for HasOne<Local, Related> {
 			.limit(1)
 	}
 
+	fn existence_query(&self, local_column: &str) -> Builder {
+		let (query, table) = existence_base::<Local, Related>();
+
+		query.where_column(
+			&format!("{table}.{}", self.foreign_key),
+			"=",
+			&format!("{}.{}", Local::TABLE_NAME, local_column),
+		)
+	}
+
 	fn r#match(&mut self, related: &[HashMap<String, Value>]) -> Result<(), Error> {
 		let related = find_related(related, &self.foreign_key, &self.value, true)?;
 
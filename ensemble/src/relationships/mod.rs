@@ -56,13 +56,17 @@ pub trait Relationship {
 	/// Get the query builder for eager loading the relationship. Not intended to be used directly.
 	fn eager_query(&self, related: Vec<Self::Key>) -> Builder;
 
+	#[doc(hidden)]
+	/// Get the query builder for checking the relationship from a query on the parent model, correlated to the given column of the parent's table. Not intended to be used directly.
+	fn existence_query(&self, local_column: &str) -> Builder;
+
 	#[doc(hidden)]
 	/// Match the eagerly loaded results to their parents. Not intended to be used directly.
 	fn r#match(&mut self, related: &[HashMap<String, Value>]) -> Result<(), Error>;
 
 	#[doc(hidden)]
-	/// Create an instance of the relationship. Not intended to be used directly.
-	fn build(value: Self::Key, related_key: Self::RelatedKey) -> Self;
+	/// Create an instance of the relationship, named after the field it's stored in. Not intended to be used directly.
+	fn build(name: &'static str, value: Self::Key, related_key: Self::RelatedKey) -> Self;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -117,6 +121,23 @@ impl<T: serde::Serialize> serde::Serialize for Status<T> {
 	}
 }
 
+/// The alias the related table is given when checking a relationship between rows of the same table.
+const SELF_RELATION_ALIAS: &str = "ensemble_reserved_0";
+
+/// Start the query for checking a relationship from a query on the parent model, along with the name to qualify the related columns with.
+///
+/// When both models use the same table, the related one is aliased, so its columns aren't mistaken for the parent's.
+fn existence_base<Local: Model, Related: Model>() -> (Builder, &'static str) {
+	if Local::TABLE_NAME != Related::TABLE_NAME {
+		return (Related::query(), Related::TABLE_NAME);
+	}
+
+	(
+		Related::query().from(&format!("{} AS {SELF_RELATION_ALIAS}", Related::TABLE_NAME)),
+		SELF_RELATION_ALIAS,
+	)
+}
+
 fn find_related<M: Model, T: serde::Serialize>(
 	related: &[HashMap<String, Value>],
 	foreign_key: &str,
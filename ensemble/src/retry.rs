@@ -0,0 +1,277 @@
+use std::{future::Future, time::Duration};
+
+use crate::{
+	connection::{self, ConnectError},
+	transaction, Error,
+};
+
+/// SQLSTATE for serialization failures, reported by `PostgreSQL` and `MySQL` (as error 1213) when a transaction deadlocks or can't be serialized.
+pub const SERIALIZATION_FAILURE: &str = "40001";
+
+/// SQLSTATE `PostgreSQL` reports when it detects a deadlock.
+pub const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Decides which failed queries are retried, how many times, and how long to wait in between.
+///
+/// By default, queries are attempted up to 3 times, waiting 50ms before the first retry and doubling the wait
+/// (up to a second) after each one. Serialization failures, deadlocks and lost connections are retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	initial_backoff: Duration,
+	max_backoff: Duration,
+	codes: Vec<String>,
+	connection_errors: bool,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			max_attempts: 3,
+			connection_errors: true,
+			max_backoff: Duration::from_secs(1),
+			initial_backoff: Duration::from_millis(50),
+			codes: vec![
+				SERIALIZATION_FAILURE.to_string(),
+				DEADLOCK_DETECTED.to_string(),
+			],
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Creates the default retry policy.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// A policy that never retries.
+	#[must_use]
+	pub fn never() -> Self {
+		Self::default().max_attempts(1)
+	}
+
+	/// Sets how many times a query is attempted in total, including the first try.
+	#[must_use]
+	pub const fn max_attempts(mut self, attempts: u32) -> Self {
+		self.max_attempts = attempts;
+		self
+	}
+
+	/// Sets the wait before the first retry, and the most the wait can grow to as it doubles after each retry.
+	#[must_use]
+	pub const fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+		self.initial_backoff = initial;
+		self.max_backoff = max;
+		self
+	}
+
+	/// Sets the SQLSTATE codes that should be retried, replacing the defaults.
+	#[must_use]
+	pub fn codes<I: IntoIterator<Item = S>, S: Into<String>>(mut self, codes: I) -> Self {
+		self.codes = codes.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Adds a SQLSTATE code that should be retried.
+	#[must_use]
+	pub fn retry_on(mut self, code: &str) -> Self {
+		self.codes.push(code.to_string());
+		self
+	}
+
+	/// Sets whether failing to connect, or losing the connection mid-query, should be retried.
+	#[must_use]
+	pub const fn retry_connection_errors(mut self, retry: bool) -> Self {
+		self.connection_errors = retry;
+		self
+	}
+
+	/// Whether the given error is transient under this policy.
+	#[must_use]
+	pub fn should_retry(&self, error: &Error) -> bool {
+		match error {
+			Error::Connection(ConnectError::Connection(_)) | Error::ConnectionLost { .. } => {
+				self.connection_errors
+			},
+			Error::Query { details } => self.is_transient(
+				details
+					.sqlstate
+					.as_deref()
+					.or_else(|| sqlite_sqlstate(&details.message)),
+				&details.message,
+			),
+			error => error
+				.details()
+				.and_then(|details| details.sqlstate.as_deref())
+				.is_some_and(|code| self.codes.iter().any(|c| c == code)),
+		}
+	}
+
+	/// Whether an unclassified error with the given SQLSTATE and message is transient under this policy.
+	fn is_transient(&self, code: Option<&str>, message: &str) -> bool {
+		code.is_some_and(|code| self.codes.iter().any(|c| c == code))
+			|| (self.connection_errors && is_connection_error(code, message))
+	}
+
+	/// How long to wait before the given retry (starting at 1).
+	#[must_use]
+	pub fn backoff_for(&self, retry: u32) -> Duration {
+		self.initial_backoff
+			.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+			.min(self.max_backoff)
+	}
+
+	/// Runs the given operation, retrying it according to this policy.
+	///
+	/// # Errors
+	///
+	/// Returns the last error if the operation fails with a non-transient error, or keeps failing after every attempt.
+	pub async fn run<T, F, Fut>(&self, operation: &str, mut callback: F) -> Result<T, Error>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut attempt = 1;
+
+		loop {
+			let error = match callback().await {
+				Ok(value) => return Ok(value),
+				Err(error) => error,
+			};
+
+			if !self.wait_to_retry(attempt, operation, &error).await {
+				return Err(error);
+			}
+
+			attempt += 1;
+		}
+	}
+
+	/// Waits before retrying after the given failed attempt. Returns `false` if the operation shouldn't be retried.
+	pub(crate) async fn wait_to_retry(&self, attempt: u32, operation: &str, error: &Error) -> bool {
+		if attempt >= self.max_attempts || !self.should_retry(error) {
+			return false;
+		}
+
+		let backoff = self.backoff_for(attempt);
+
+		tracing::warn!(
+			attempt,
+			operation,
+			error = %error,
+			backoff = ?backoff,
+			max_attempts = self.max_attempts,
+			"Retrying after a transient database error."
+		);
+
+		tokio::time::sleep(backoff).await;
+
+		true
+	}
+}
+
+/// The policy reads on the named connection are retried with. Reads inside a transaction are never retried
+/// on their own, since a failure aborts the whole transaction.
+pub(crate) fn for_reads(name: &str) -> RetryPolicy {
+	if transaction::is_active(name) {
+		return RetryPolicy::never();
+	}
+
+	connection::retry_policy(name).unwrap_or_else(RetryPolicy::never)
+}
+
+/// Extracts the SQLSTATE code from a `PostgreSQL` or `MySQL` error message.
+pub(crate) fn sqlstate(message: &str) -> Option<&str> {
+	let is_code = |code: &str| code.len() == 5 && code.bytes().all(|b| b.is_ascii_alphanumeric());
+
+	// PostgreSQL errors are reported as the debug representation of the raw error fields, where the code is prefixed by a C.
+	if let Some((_, rest)) = message.split_once("\\0C") {
+		return rest.get(..5).filter(|code| is_code(code));
+	}
+
+	// MySQL errors look like `1213 (40001): Deadlock found when trying to get lock`.
+	let (number, rest) = message.split_once(" (")?;
+	let (code, _) = rest.split_once("):")?;
+
+	(number.bytes().all(|b| b.is_ascii_digit()) && is_code(code)).then_some(code)
+}
+
+/// `SQLite` doesn't report SQLSTATE codes, so its lock contention errors are treated as serialization failures.
+fn sqlite_sqlstate(message: &str) -> Option<&'static str> {
+	(message.contains("database is locked") || message.contains("database table is locked"))
+		.then_some(SERIALIZATION_FAILURE)
+}
+
+pub(crate) fn is_connection_error(code: Option<&str>, message: &str) -> bool {
+	const MESSAGES: [&str; 6] = [
+		"broken pipe",
+		"connection reset",
+		"connection refused",
+		"connection closed",
+		"unexpected eof",
+		"conn is drop",
+	];
+
+	// SQLSTATE class 08 covers connection exceptions.
+	if code.is_some_and(|code| code.starts_with("08")) {
+		return true;
+	}
+
+	let message = message.to_ascii_lowercase();
+	MESSAGES.iter().any(|m| message.contains(m))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn extracts_sqlstate_codes() {
+		assert_eq!(
+			sqlstate(
+				r#"db:Notice { storage: b"SERROR\0VERROR\0C40P01\0Mdeadlock detected\0", severity: Error }"#
+			),
+			Some("40P01")
+		);
+		assert_eq!(
+			sqlstate("1213 (40001): Deadlock found when trying to get lock"),
+			Some("40001")
+		);
+		assert_eq!(sqlstate("no such table: users"), None);
+	}
+
+	#[test]
+	fn decides_which_errors_to_retry() {
+		let policy = RetryPolicy::new();
+		let classify = |message: &str| {
+			crate::error::classify(
+				&rbatis::Error::from(message),
+				"update accounts set balance = ?",
+				&[],
+			)
+		};
+
+		assert!(policy.should_retry(&classify(
+			"1213 (40001): Deadlock found when trying to get lock"
+		)));
+		assert!(policy.should_retry(&classify("database is locked")));
+		assert!(policy.should_retry(&classify("Broken pipe (os error 32)")));
+		assert!(!policy.should_retry(&classify("1062 (23000): Duplicate entry")));
+		assert!(!policy.should_retry(&classify("no such table: accounts")));
+		assert!(!policy
+			.retry_connection_errors(false)
+			.should_retry(&classify("Broken pipe (os error 32)")));
+	}
+
+	#[test]
+	fn backs_off_exponentially() {
+		let policy =
+			RetryPolicy::new().backoff(Duration::from_millis(100), Duration::from_millis(300));
+
+		assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+		assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+		assert_eq!(policy.backoff_for(3), Duration::from_millis(300));
+	}
+}
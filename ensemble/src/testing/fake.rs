@@ -0,0 +1,275 @@
+use futures::future::BoxFuture;
+use rbatis::{
+	rbdc::{
+		db::{Connection, ExecResult, Row},
+		pool::{conn_manager::ConnManager, Pool},
+		Error,
+	},
+	RBatis,
+};
+use rbs::Value;
+use serde::Serialize;
+use std::{
+	collections::VecDeque,
+	future::Future,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use crate::connection::{self, Database};
+
+/// A query sent to a [`FakeConnection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedQuery {
+	/// The SQL that was sent.
+	pub sql: String,
+	/// The values bound to the query.
+	pub bindings: Vec<Value>,
+}
+
+/// An in-process stand-in for a database, which records every query it receives and answers them with canned responses.
+///
+/// Responses are queued up with [`push_rows`](Self::push_rows), [`push_result`](Self::push_result) and [`push_error`](Self::push_error),
+/// and handed out in order. Once the queue is empty, queries return no rows and affect no rows.
+/// Transaction statements (like `BEGIN` and `COMMIT`) are recorded, but always succeed without taking a response from the queue.
+///
+/// ## Example
+///
+/// ```rust
+/// # use ensemble::{testing::FakeConnection, Model};
+/// # #[derive(Debug, Model)]
+/// # struct User {
+/// #   id: u64,
+/// #   name: String,
+/// # }
+/// # ensemble::testing::run(async {
+/// let fake = FakeConnection::postgres();
+/// fake.push_rows([User { id: 1, name: "Alice".to_string() }]);
+///
+/// let user = fake.scope(User::find(1)).await?;
+///
+/// assert_eq!(user.name, "Alice");
+/// assert_eq!(fake.sql(), [r#"SELECT * FROM users WHERE "id" = ? LIMIT 1"#]);
+/// # Ok::<(), ensemble::Error>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FakeConnection {
+	database: Database,
+	state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	queries: Vec<RecordedQuery>,
+	responses: VecDeque<Response>,
+}
+
+#[derive(Debug)]
+enum Response {
+	Rows(Vec<Value>),
+	Result(ExecResult),
+	Error(String),
+}
+
+impl FakeConnection {
+	/// Creates a fake that generates SQL for `MySQL`.
+	#[must_use]
+	pub fn mysql() -> Self {
+		Self::new(Database::MySQL)
+	}
+
+	/// Creates a fake that generates SQL for `PostgreSQL`.
+	#[must_use]
+	pub fn postgres() -> Self {
+		Self::new(Database::PostgreSQL)
+	}
+
+	/// Creates a fake that generates SQL for `SQLite`.
+	#[must_use]
+	pub fn sqlite() -> Self {
+		Self::new(Database::SQLite)
+	}
+
+	fn new(database: Database) -> Self {
+		Self {
+			database,
+			state: Arc::default(),
+		}
+	}
+
+	/// Runs the given future with this fake in place of the default connection. Only queries made by the current task go to the fake.
+	pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+		self.scope_on(connection::DEFAULT, future).await
+	}
+
+	/// Runs the given future with this fake in place of the named connection. Only queries made by the current task go to the fake.
+	///
+	/// # Panics
+	///
+	/// Panics if the fake can't be set up as a pool, which never happens.
+	pub async fn scope_on<F: Future>(&self, name: &str, future: F) -> F::Output {
+		let rb = RBatis::new();
+		rb.init_pool(FakePool(self.clone()))
+			.expect("Failed to set up the fake pool.");
+
+		connection::with_fake(name, self.database, rb, future).await
+	}
+
+	/// Queues the rows the next query should return.
+	///
+	/// # Panics
+	///
+	/// Panics if any of the rows can't be serialized.
+	pub fn push_rows<T: Serialize, I: IntoIterator<Item = T>>(&self, rows: I) {
+		let rows = rows
+			.into_iter()
+			.map(|row| rbs::to_value(row).expect("Failed to serialize fake row."))
+			.collect();
+
+		self.push(Response::Rows(rows));
+	}
+
+	/// Queues the result the next statement should return.
+	pub fn push_result<V: Into<Value>>(&self, rows_affected: u64, last_insert_id: V) {
+		self.push(Response::Result(ExecResult {
+			rows_affected,
+			last_insert_id: last_insert_id.into(),
+		}));
+	}
+
+	/// Queues an error the next query should fail with, as reported by the driver (e.g. `UNIQUE constraint failed: users.email`).
+	pub fn push_error(&self, message: &str) {
+		self.push(Response::Error(message.to_string()));
+	}
+
+	/// Returns every query the fake has received so far, in order.
+	///
+	/// # Panics
+	///
+	/// Panics if another thread panicked while using the fake.
+	#[must_use]
+	pub fn queries(&self) -> Vec<RecordedQuery> {
+		self.state.lock().unwrap().queries.clone()
+	}
+
+	/// Returns the SQL of every query the fake has received so far, in order.
+	#[must_use]
+	pub fn sql(&self) -> Vec<String> {
+		self.queries().into_iter().map(|query| query.sql).collect()
+	}
+
+	fn push(&self, response: Response) {
+		self.state.lock().unwrap().responses.push_back(response);
+	}
+
+	/// Records the query, and takes the response to it from the queue.
+	fn respond(&self, sql: &str, bindings: Vec<Value>) -> Option<Response> {
+		let mut state = self.state.lock().unwrap();
+
+		state.queries.push(RecordedQuery {
+			bindings,
+			sql: sql.to_string(),
+		});
+
+		if is_transaction_statement(sql) {
+			return None;
+		}
+
+		state.responses.pop_front()
+	}
+}
+
+fn is_transaction_statement(sql: &str) -> bool {
+	let keyword = sql.split_whitespace().next().unwrap_or_default();
+
+	["BEGIN", "COMMIT", "ROLLBACK", "SAVEPOINT", "RELEASE"]
+		.iter()
+		.any(|statement| keyword.eq_ignore_ascii_case(statement))
+}
+
+impl Connection for FakeConnection {
+	fn get_rows(
+		&mut self,
+		_: &str,
+		_: Vec<Value>,
+	) -> BoxFuture<'_, Result<Vec<Box<dyn Row>>, Error>> {
+		Box::pin(async { Err(Error::from("The fake connection only returns values.")) })
+	}
+
+	fn get_values(
+		&mut self,
+		sql: &str,
+		params: Vec<Value>,
+	) -> BoxFuture<'_, Result<Vec<Value>, Error>> {
+		let response = self.respond(sql, params);
+
+		Box::pin(async move {
+			match response {
+				None => Ok(vec![]),
+				Some(Response::Rows(rows)) => Ok(rows),
+				Some(Response::Error(message)) => Err(Error::from(message)),
+				Some(Response::Result(_)) => Err(Error::from(
+					"Expected rows to be queued for a query, but found a statement result.",
+				)),
+			}
+		})
+	}
+
+	fn exec(&mut self, sql: &str, params: Vec<Value>) -> BoxFuture<'_, Result<ExecResult, Error>> {
+		let response = self.respond(sql, params);
+
+		Box::pin(async move {
+			match response {
+				None => Ok(ExecResult {
+					rows_affected: 0,
+					last_insert_id: Value::Null,
+				}),
+				Some(Response::Result(result)) => Ok(result),
+				Some(Response::Error(message)) => Err(Error::from(message)),
+				Some(Response::Rows(_)) => Err(Error::from(
+					"Expected a result to be queued for a statement, but found rows.",
+				)),
+			}
+		})
+	}
+
+	fn ping(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+		Box::pin(async { Ok(()) })
+	}
+
+	fn close(&mut self) -> BoxFuture<'_, Result<(), Error>> {
+		Box::pin(async { Ok(()) })
+	}
+}
+
+/// Hands out handles to the same fake, so every connection taken from the pool shares its queries and responses.
+#[derive(Debug)]
+struct FakePool(FakeConnection);
+
+#[async_trait::async_trait]
+impl Pool for FakePool {
+	fn new(_: ConnManager) -> Result<Self, Error> {
+		Err(Error::from(
+			"Fake pools can't be created from a connection manager.",
+		))
+	}
+
+	async fn get(&self) -> Result<Box<dyn Connection>, Error> {
+		Ok(Box::new(self.0.clone()))
+	}
+
+	async fn get_timeout(&self, _: Duration) -> Result<Box<dyn Connection>, Error> {
+		self.get().await
+	}
+
+	async fn set_conn_max_lifetime(&self, _: Option<Duration>) {}
+
+	async fn set_max_idle_conns(&self, _: u64) {}
+
+	async fn set_max_open_conns(&self, _: u64) {}
+
+	fn driver_type(&self) -> &'static str {
+		"fake"
+	}
+}
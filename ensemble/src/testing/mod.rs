@@ -0,0 +1,104 @@
+//! Helpers for running tests against a real database, isolating each of them in a transaction that is rolled back when it ends.
+//!
+//! Since no test ever commits, tests can run in parallel against the same database without seeing each other's rows.
+//! Most of the time, you'll want to use the [`#[ensemble::test]`](crate::test) attribute instead of calling these directly.
+//!
+//! To test the queries Ensemble makes without a database at all, use a [`FakeConnection`].
+
+use std::{future::Future, sync::OnceLock};
+use tokio::runtime::Runtime;
+
+use crate::{connection, transaction};
+pub use fake::{FakeConnection, RecordedQuery};
+
+mod fake;
+
+/// Runs the given future to completion on a runtime shared by every test.
+///
+/// Pooled connections are tied to the runtime that opened them, so tests sharing a pool need to share a runtime too
+/// (which rules out `#[tokio::test]`, since it starts a new runtime for every test).
+///
+/// # Panics
+///
+/// Panics if the runtime can't be started.
+pub fn run<F: Future>(future: F) -> F::Output {
+	static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+	RUNTIME
+		.get_or_init(|| Runtime::new().expect("Failed to start test runtime."))
+		.block_on(future)
+}
+
+/// Sets up the default connection from the environment (see [`SetupOptions::from_env`](crate::SetupOptions::from_env)),
+/// unless it has already been set up.
+///
+/// # Panics
+///
+/// Panics if the environment doesn't describe a valid connection, or if the database can't be reached.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub async fn setup_from_env() {
+	static SETUP: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
+	SETUP
+		.get_or_init(|| async {
+			let options =
+				crate::SetupOptions::from_env().expect("Failed to read database options.");
+
+			match crate::setup_with(options).await {
+				Ok(()) | Err(crate::SetupError::AlreadyInitialized(_)) => {},
+				Err(e) => panic!("Failed to set up database pool: {e}"),
+			}
+		})
+		.await;
+}
+
+/// Runs the given closure inside a transaction on the default connection, which is rolled back once it finishes.
+///
+/// Every query the closure runs on the default connection (including the ones made by models) goes through the transaction,
+/// and calls to [`transaction`](crate::transaction) inside it become savepoints. Tasks spawned by the closure don't take part in it.
+///
+/// ## Example
+///
+/// ```rust
+/// # use ensemble::Model;
+/// # #[derive(Debug, Model)]
+/// # struct User {
+/// #   id: u64,
+/// #   name: String,
+/// # }
+/// # async fn call() {
+/// ensemble::testing::isolated(|| async {
+///     let user = User { name: "Alice".to_string(), ..User::default() }.create().await.unwrap();
+///
+///     assert_eq!(User::find(user.id).await.unwrap().name, "Alice");
+/// })
+/// .await;
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the transaction can't be started or rolled back, or if the closure panics.
+pub async fn isolated<F, Fut, T>(callback: F) -> T
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = T>,
+{
+	isolated_on(connection::DEFAULT, callback).await
+}
+
+/// Runs the given closure inside a transaction on the named connection, which is rolled back once it finishes.
+/// See [`isolated`] for details.
+///
+/// # Panics
+///
+/// Panics if the transaction can't be started or rolled back, or if the closure panics.
+pub async fn isolated_on<F, Fut, T>(name: &str, callback: F) -> T
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = T>,
+{
+	transaction::rolled_back(name, callback)
+		.await
+		.unwrap_or_else(|e| panic!("Failed to isolate test on the {name} connection: {e}"))
+}
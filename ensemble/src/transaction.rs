@@ -0,0 +1,365 @@
+use futures::FutureExt;
+use std::{
+	cell::RefCell,
+	collections::HashMap,
+	future::Future,
+	panic::{self, AssertUnwindSafe},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
+use tokio::sync::Mutex;
+
+use crate::{
+	connection::{self, Connection, ConnectionGuard},
+	error,
+	retry::RetryPolicy,
+	Error,
+};
+
+tokio::task_local! {
+	/// The transactions the current task has open, by connection name.
+	static TRANSACTIONS: RefCell<HashMap<String, Arc<Transaction>>>;
+}
+
+/// A transaction open on a single connection, shared by every query the task runs on it.
+struct Transaction {
+	connection: Arc<Mutex<Connection>>,
+	depth: AtomicUsize,
+}
+
+impl Transaction {
+	async fn execute(&self, sql: &str) -> Result<(), Error> {
+		tracing::debug!(sql = sql, "Executing transaction SQL query");
+
+		self.connection
+			.lock()
+			.await
+			.exec(sql, vec![])
+			.await
+			.map_err(|e| error::classify(&e, sql, &[]))?;
+
+		Ok(())
+	}
+}
+
+/// Runs the given closure inside a transaction on the default connection.
+///
+/// Every query the closure runs on the default connection (including the ones made by models) goes through the transaction.
+/// It is committed if the closure returns `Ok`, and rolled back if it returns `Err` or panics.
+/// Nested transactions are mapped to savepoints, so they can be rolled back without affecting the outer transaction.
+///
+/// ## Example
+///
+/// ```rust
+/// # use ensemble::Model;
+/// # #[derive(Debug, Model)]
+/// # struct Account {
+/// #   id: u64,
+/// #   balance: u64,
+/// # }
+/// # async fn call() -> Result<(), ensemble::Error> {
+/// ensemble::transaction(|| async {
+///     let mut from = Account::find(1).await?;
+///     let mut to = Account::find(2).await?;
+///
+///     from.balance -= 100;
+///     to.balance += 100;
+///
+///     from.save().await?;
+///     to.save().await
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns the error returned by the closure, or an error if the transaction could not be started or committed.
+pub async fn transaction<F, Fut, T, E>(callback: F) -> Result<T, E>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<T, E>>,
+	E: From<Error>,
+{
+	transaction_on(connection::DEFAULT, callback).await
+}
+
+/// Runs the given closure inside a transaction on the named connection. See [`transaction`] for details.
+///
+/// # Errors
+///
+/// Returns the error returned by the closure, or an error if the transaction could not be started or committed.
+pub async fn transaction_on<F, Fut, T, E>(name: &str, callback: F) -> Result<T, E>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<T, E>>,
+	E: From<Error>,
+{
+	if TRANSACTIONS.try_with(|_| ()).is_ok() {
+		return run(name, callback).await;
+	}
+
+	TRANSACTIONS
+		.scope(RefCell::default(), run(name, callback))
+		.await
+}
+
+/// Runs the given closure inside a transaction on the default connection, retrying it if it fails with a transient error.
+///
+/// The closure runs again from the start whenever it fails with an error the connection's [`RetryPolicy`] considers transient
+/// (like a deadlock). See [`transaction`] for details.
+///
+/// Since the closure may run more than once, it shouldn't have side effects outside of the database.
+/// When nested inside another transaction on the same connection it only runs once, as retrying is up to the outer transaction.
+///
+/// # Errors
+///
+/// Returns the error returned by the closure's last attempt, or an error if the transaction could not be started or committed.
+pub async fn retry_transaction<F, Fut, T>(callback: F) -> Result<T, Error>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, Error>>,
+{
+	retry_transaction_on(connection::DEFAULT, callback).await
+}
+
+/// Runs the given closure inside a transaction on the named connection, retrying transient failures.
+/// See [`retry_transaction`] for details.
+///
+/// # Errors
+///
+/// Returns the error returned by the closure's last attempt, or an error if the transaction could not be started or committed.
+pub async fn retry_transaction_on<F, Fut, T>(name: &str, mut callback: F) -> Result<T, Error>
+where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<T, Error>>,
+{
+	if is_active(name) {
+		return transaction_on(name, callback).await;
+	}
+
+	let policy = connection::retry_policy(name).unwrap_or_else(RetryPolicy::never);
+	let mut attempt = 1;
+
+	loop {
+		let error = match transaction_on(name, &mut callback).await {
+			Ok(value) => return Ok(value),
+			Err(error) => error,
+		};
+
+		if !policy.wait_to_retry(attempt, "transaction", &error).await {
+			return Err(error);
+		}
+
+		attempt += 1;
+	}
+}
+
+/// Runs the given closure inside a transaction on the named connection that is always rolled back, even if the closure succeeds.
+/// Used to isolate tests from each other.
+#[cfg(feature = "testing")]
+pub async fn rolled_back<F, Fut, T>(name: &str, callback: F) -> Result<T, Error>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = T>,
+{
+	let run = async {
+		let scope = open(name).await?;
+		let result = AssertUnwindSafe(callback()).catch_unwind().await;
+		let rollback = scope.rollback().await;
+
+		match result {
+			Ok(value) => rollback.map(|()| value),
+			Err(payload) => panic::resume_unwind(payload),
+		}
+	};
+
+	if TRANSACTIONS.try_with(|_| ()).is_ok() {
+		return run.await;
+	}
+
+	TRANSACTIONS.scope(RefCell::default(), run).await
+}
+
+/// Opens a transaction on the named connection, or a savepoint if the task already has one open.
+async fn open(name: &str) -> Result<Scope, Error> {
+	let existing = TRANSACTIONS.with(|transactions| transactions.borrow().get(name).cloned());
+
+	let scope = if let Some(transaction) = existing {
+		let depth = transaction.depth.fetch_add(1, Ordering::SeqCst) + 1;
+
+		Scope::new(name, transaction, depth)
+	} else {
+		let transaction = Arc::new(Transaction {
+			depth: AtomicUsize::new(0),
+			connection: Arc::new(Mutex::new(connection::get_pooled(name).await?)),
+		});
+
+		TRANSACTIONS.with(|transactions| {
+			transactions
+				.borrow_mut()
+				.insert(name.to_string(), transaction.clone());
+		});
+
+		Scope::new(name, transaction, 0)
+	};
+
+	scope.begin().await?;
+
+	Ok(scope)
+}
+
+async fn run<F, Fut, T, E>(name: &str, callback: F) -> Result<T, E>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<T, E>>,
+	E: From<Error>,
+{
+	let scope = open(name).await?;
+
+	let result = AssertUnwindSafe(callback()).catch_unwind().await;
+
+	match result {
+		Ok(Ok(value)) => {
+			scope.commit().await?;
+
+			Ok(value)
+		},
+		Ok(Err(error)) => {
+			if let Err(e) = scope.rollback().await {
+				tracing::error!(error = ?e, "Failed to roll back transaction.");
+			}
+
+			Err(error)
+		},
+		Err(payload) => {
+			if let Err(e) = scope.rollback().await {
+				tracing::error!(error = ?e, "Failed to roll back transaction.");
+			}
+
+			panic::resume_unwind(payload)
+		},
+	}
+}
+
+/// Whether the current task has a transaction open on the named connection.
+pub fn is_active(name: &str) -> bool {
+	TRANSACTIONS
+		.try_with(|transactions| transactions.borrow().contains_key(name))
+		.unwrap_or(false)
+}
+
+/// Returns the connection of the transaction the current task has open on the named connection, if any.
+pub async fn connection(name: &str) -> Option<ConnectionGuard> {
+	let transaction = TRANSACTIONS
+		.try_with(|transactions| transactions.borrow().get(name).cloned())
+		.ok()
+		.flatten()?;
+
+	Some(ConnectionGuard::Transaction(
+		transaction.connection.clone().lock_owned().await,
+	))
+}
+
+/// A single level of a transaction: the transaction itself at depth 0, or a savepoint inside it.
+///
+/// Dropping the scope closes the level. If it is dropped before finishing (because the future running it was cancelled),
+/// it is rolled back in the background: the whole transaction so the connection doesn't go back to the pool mid-transaction,
+/// or just the savepoint so the cancelled writes aren't committed with the outer transaction.
+struct Scope {
+	depth: usize,
+	name: String,
+	finished: bool,
+	transaction: Arc<Transaction>,
+}
+
+impl Scope {
+	fn new(name: &str, transaction: Arc<Transaction>, depth: usize) -> Self {
+		Self {
+			depth,
+			transaction,
+			finished: false,
+			name: name.to_string(),
+		}
+	}
+
+	fn savepoint(&self) -> String {
+		format!("ensemble_savepoint_{}", self.depth)
+	}
+
+	async fn begin(&self) -> Result<(), Error> {
+		if self.depth == 0 {
+			return self.transaction.execute("BEGIN").await;
+		}
+
+		let sql = connection::grammar(&self.name).compile_savepoint(&self.savepoint());
+		self.transaction.execute(&sql).await
+	}
+
+	async fn commit(mut self) -> Result<(), Error> {
+		self.finished = true;
+
+		if self.depth != 0 {
+			let sql = connection::grammar(&self.name).compile_release_savepoint(&self.savepoint());
+			return self.transaction.execute(&sql).await;
+		}
+
+		let result = self.transaction.execute("COMMIT").await;
+		if result.is_err() {
+			let _ = self.transaction.execute("ROLLBACK").await;
+		}
+
+		result
+	}
+
+	async fn rollback(mut self) -> Result<(), Error> {
+		self.finished = true;
+
+		if self.depth == 0 {
+			return self.transaction.execute("ROLLBACK").await;
+		}
+
+		let sql = connection::grammar(&self.name).compile_rollback_to_savepoint(&self.savepoint());
+		self.transaction.execute(&sql).await
+	}
+}
+
+impl Drop for Scope {
+	fn drop(&mut self) {
+		if self.depth == 0 {
+			let _ =
+				TRANSACTIONS.try_with(|transactions| transactions.borrow_mut().remove(&self.name));
+		} else {
+			self.transaction.depth.fetch_sub(1, Ordering::SeqCst);
+		}
+
+		if self.finished {
+			return;
+		}
+
+		let sql = if self.depth == 0 {
+			"ROLLBACK".to_string()
+		} else {
+			connection::grammar(&self.name).compile_rollback_to_savepoint(&self.savepoint())
+		};
+
+		// the connection is locked right away if possible, so the rollback runs before anything the outer transaction does next
+		let connection = self.transaction.connection.clone();
+		let guard = connection.clone().try_lock_owned().ok();
+		if let Ok(handle) = tokio::runtime::Handle::try_current() {
+			handle.spawn(async move {
+				let mut conn = match guard {
+					Some(guard) => guard,
+					None => connection.lock_owned().await,
+				};
+
+				if let Err(e) = conn.exec(&sql, vec![]).await {
+					tracing::error!(error = ?e, "Failed to roll back cancelled transaction.");
+				}
+			});
+		}
+	}
+}
@@ -57,9 +57,7 @@ impl<'de> Deserialize<'de> for DateTime {
 						.map_err(|e| D::Error::custom(e.to_string()))?,
 				)
 			}),
-			_ => Err(D::Error::custom(
-				&format!("unsupported type DateTime({v})",),
-			)),
+			_ => Err(D::Error::custom(format!("unsupported type DateTime({v})"))),
 		}
 	}
 }
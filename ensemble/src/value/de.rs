@@ -23,24 +23,18 @@ trait ValueBase<'de>: Deserializer<'de, Error = rbs::Error> {
 	type Iter: ExactSizeIterator<Item = Self::Item>;
 	type MapIter: Iterator<Item = (Self::Item, Self::Item)>;
 
-	fn is_null(&self) -> bool;
 	fn unexpected(&self) -> Unexpected<'_>;
 
 	fn into_iter(self) -> Result<Self::Iter, Self::Item>;
 	fn into_map_iter(self) -> Result<Self::MapIter, Self::Item>;
 }
 
-impl<'de> ValueBase<'de> for Value {
+impl ValueBase<'_> for Value {
 	type Item = ValueDeserializer;
 	type Iter = IntoIter<Self::Item>;
 	type MapIter = IntoIter<(Self::Item, Self::Item)>;
 	type MapDeserializer = MapDeserializer<Self::MapIter, Self::Item>;
 
-	#[inline]
-	fn is_null(&self) -> bool {
-		matches!(self, Self::Null)
-	}
-
 	#[inline]
 	fn into_iter(self) -> Result<Self::Iter, Self::Item> {
 		match self {
@@ -85,17 +79,12 @@ impl<'de> ValueBase<'de> for Value {
 	}
 }
 
-impl<'de> ValueBase<'de> for ValueDeserializer {
+impl ValueBase<'_> for ValueDeserializer {
 	type Item = Self;
 	type Iter = IntoIter<Self::Item>;
 	type MapIter = IntoIter<(Self::Item, Self::Item)>;
 	type MapDeserializer = MapDeserializer<Self::MapIter, Self::Item>;
 
-	#[inline]
-	fn is_null(&self) -> bool {
-		self.0.is_null()
-	}
-
 	#[inline]
 	fn into_iter(self) -> Result<Self::Iter, Self::Item> {
 		match self.0 {
@@ -425,8 +414,23 @@ impl<'de> Deserializer<'de> for ValueDeserializer {
 		}
 	}
 
+	#[inline]
+	fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+	where
+		V: Visitor<'de>,
+	{
+		// MySQL and SQLite don't have a native boolean type, and store them as integers instead.
+		match self.0 {
+			Value::I32(v) => visitor.visit_bool(v != 0),
+			Value::I64(v) => visitor.visit_bool(v != 0),
+			Value::U32(v) => visitor.visit_bool(v != 0),
+			Value::U64(v) => visitor.visit_bool(v != 0),
+			_ => self.deserialize_any(visitor),
+		}
+	}
+
 	forward_to_deserialize_any! {
-		bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
+		u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string unit seq
 		bytes byte_buf map tuple_struct struct
 		identifier tuple ignored_any
 	}
@@ -777,6 +781,13 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn deserializes_booleans_stored_as_integers() {
+		assert!(deserialize_value::<bool>(Value::I64(1)).unwrap());
+		assert!(!deserialize_value::<bool>(Value::I32(0)).unwrap());
+		assert!(deserialize_value::<bool>(Value::Bool(true)).unwrap());
+	}
+
 	#[test]
 	fn unit_struct_requires_an_empty_array() {
 		assert_eq!(
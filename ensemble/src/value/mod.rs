@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
 
 use self::{de::deserialize_value, ser::fast_serialize};
 use crate::Model;
@@ -24,6 +24,15 @@ pub(crate) fn from<M: Model>(value: rbs::Value) -> Result<M, rbs::Error> {
 	deserialize_value::<M>(value)
 }
 
+/// Deserialize a value (like the result of an aggregate) from the database.
+///
+/// # Errors
+///
+/// Returns an error if deserialization fails.
+pub(crate) fn from_db<T: DeserializeOwned>(value: rbs::Value) -> Result<T, rbs::Error> {
+	deserialize_value::<T>(value)
+}
+
 pub(crate) fn serializing_for_db<S: serde::Serializer>() -> bool {
 	std::any::type_name::<S::Error>() == std::any::type_name::<rbs::Error>()
 }
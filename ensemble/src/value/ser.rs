@@ -240,11 +240,6 @@ pub struct DefaultSerializeMap {
 	next_key: Option<Value>,
 }
 
-pub struct SerializeStructVariant {
-	idx: u32,
-	vec: Vec<Value>,
-}
-
 impl ser::SerializeSeq for SerializeVec {
 	type Ok = Value;
 	type Error = rbs::Error;
@@ -392,29 +387,6 @@ impl ser::SerializeStruct for SerializeVec {
 	}
 }
 
-impl ser::SerializeStructVariant for SerializeStructVariant {
-	type Ok = Value;
-	type Error = rbs::Error;
-
-	#[inline]
-	fn serialize_field<T: Serialize + ?Sized>(
-		&mut self,
-		_key: &'static str,
-		value: &T,
-	) -> Result<(), Self::Error> {
-		self.vec.push(fast_serialize(value)?);
-		Ok(())
-	}
-
-	#[inline]
-	fn end(self) -> Result<Value, Self::Error> {
-		Ok(Value::Array(vec![
-			Value::from(self.idx),
-			Value::Array(self.vec),
-		]))
-	}
-}
-
 #[cfg(test)]
 mod tests {
 	use crate::types::{DateTime, Hashed, Json, Uuid};
@@ -104,22 +104,23 @@ fn impl_set(fields: &Fields) -> syn::Result<TokenStream> {
 				.attr
 				.rename
 				.as_ref()
-				.map_or(iden.clone(), |s| Ident::new(s, iden.span()));
+				.map_or_else(|| iden.clone(), |s| Ident::new(s, iden.span()));
 
 			let types_constraint = if only_types.is_empty() {
 				TokenStream::new()
 			} else {
 				quote_spanned! {
-					f.span()=> if !matches!(self.r#type, #(#only_types)|*) {
-						panic!("{} is not a valid option for {} columns.", stringify!(#iden), self.r#type);
-					}
+					f.span()=> assert!(
+						matches!(self.r#type, #(#only_types)|*),
+						"{} is not a valid option for {} columns.", stringify!(#iden), self.r#type
+					);
 				}
 			};
 
 			let needs = build_needs(f)?;
 
 			Ok(quote_spanned! {f.span()=>
-				#[allow(clippy::return_self_not_must_use, clippy::must_use_candidate)]
+				#[allow(clippy::return_self_not_must_use, clippy::must_use_candidate, clippy::missing_panics_doc)]
 				#doc
 				pub fn #alias #fn_constrain (mut self, #iden: #fn_ty) -> Self {
 					#types_constraint
@@ -178,9 +179,7 @@ fn build_needs(field: &Field) -> syn::Result<TokenStream> {
 
 	let iden = &field.ident;
 	Ok(quote_spanned! {field.span()=>
-		if #tokens {
-			panic!("{} requires one of {} to be set.", stringify!(#iden), stringify!(#needs));
-		}
+		assert!(!(#tokens), "{} requires one of {} to be set.", stringify!(#iden), stringify!(#needs));
 	})
 }
 
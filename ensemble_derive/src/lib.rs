@@ -1,10 +1,11 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery)]
 
 use std::fmt::Display;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, DeriveInput, ItemFn};
 
 mod column;
 mod model;
+mod test;
 
 #[proc_macro_derive(Model, attributes(ensemble, model, validate))]
 pub fn derive_model(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -28,6 +29,32 @@ pub fn derive_column(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 		.into()
 }
 
+/// Runs an async test inside a transaction that is rolled back when it ends, so it can't leak rows into other tests.
+///
+/// The default connection is set up from the environment, unless a `setup` function is given (which should be async,
+/// and only set things up the first time it's called). Pass `connection` to isolate a named connection instead of the default one.
+///
+/// ```rust,ignore
+/// #[ensemble::test(setup = crate::setup, connection = "analytics")]
+/// async fn records_events() {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(
+	args: proc_macro::TokenStream,
+	input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let opts = match deluxe::parse(args) {
+		Ok(opts) => opts,
+		Err(e) => return e.into_compile_error().into(),
+	};
+
+	test::r#impl(opts, parse_macro_input!(input as ItemFn))
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
 #[derive(Clone, Copy)]
 pub(crate) enum Relationship {
 	HasOne,
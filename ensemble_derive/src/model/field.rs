@@ -38,6 +38,7 @@ pub struct Attr {
 	#[cfg(feature = "json")]
 	pub show: bool,
 	pub primary: bool,
+	pub aggregate: bool,
 	pub column: Option<String>,
 	pub local_key: Option<String>,
 	pub foreign_key: Option<String>,
@@ -85,6 +86,8 @@ impl Field {
 				Value::Expr(expr) => Some(quote_spanned! { self.span() => #expr }),
 				Value::Default => Some(quote_spanned! { self.span() => Default::default() }),
 			}
+		} else if self.attr.aggregate {
+			Some(quote_spanned! { self.span() => Default::default() })
 		} else if attrs.uuid {
 			let Type::Path(ty) = &self.ty else {
 				return Err(syn::Error::new_spanned(
@@ -115,6 +118,7 @@ impl Field {
 		} else if let Some((relationship_type, related, _)) = self.relationship(primary_key) {
 			let relationship_ident = Ident::new(&relationship_type.to_string(), self.span());
 			let foreign_key = self.foreign_key(relationship_type, &related);
+			let field_name = self.ident.to_string();
 
 			if self.attr.column == Some(self.ident.to_string()) {
 				return Err(syn::Error::new_spanned(
@@ -124,7 +128,7 @@ impl Field {
 			}
 
 			Some(
-				quote_spanned! { self.span() => <#relationship_ident<#name, #related>>::build(Default::default(), #foreign_key) },
+				quote_spanned! { self.span() => <#relationship_ident<#name, #related>>::build(#field_name, Default::default(), #foreign_key) },
 			)
 		} else if self.ty.to_token_stream().to_string().starts_with("Option") {
 			Some(quote_spanned! { self.span() => None })
@@ -173,9 +177,7 @@ impl Field {
 			return None;
 		};
 
-		let Some(ty) = ty.path.segments.first() else {
-			return None;
-		};
+		let ty = ty.path.segments.first()?;
 
 		let relationship_type = ty.ident.to_string();
 		if !["HasOne", "HasMany", "BelongsTo", "BelongsToMany"]
@@ -18,6 +18,7 @@ mod serde;
 pub struct Opts {
 	#[deluxe(rename = table)]
 	table_name: Option<String>,
+	connection: Option<String>,
 }
 
 pub fn r#impl(ast: &DeriveInput, opts: Opts) -> syn::Result<proc_macro2::TokenStream> {
@@ -41,6 +42,7 @@ pub fn r#impl(ast: &DeriveInput, opts: Opts) -> syn::Result<proc_macro2::TokenSt
 	let find_impl = impl_find(primary_key);
 	let fresh_impl = impl_fresh(primary_key);
 	let eager_load_impl = impl_eager_load(&fields);
+	let existence_query_impl = impl_existence_query(&ast.ident, &fields, primary_key);
 	let save_impl = impl_save(&fields, primary_key);
 	let primary_key_impl = impl_primary_key(primary_key);
 	let fill_relation_impl = impl_fill_relation(&fields);
@@ -49,6 +51,7 @@ pub fn r#impl(ast: &DeriveInput, opts: Opts) -> syn::Result<proc_macro2::TokenSt
 	let create_impl = impl_create(&ast.ident, &fields, primary_key);
 	let relationships_impl = impl_relationships(&ast.ident, &fields)?;
 	let table_name_impl = impl_table_name(&ast.ident.to_string(), opts.table_name);
+	let connection_impl = impl_connection(opts.connection);
 
 	let name = &ast.ident;
 	let primary_key_type = &primary_key.ty;
@@ -65,7 +68,9 @@ pub fn r#impl(ast: &DeriveInput, opts: Opts) -> syn::Result<proc_macro2::TokenSt
 				#fresh_impl
 				#create_impl
 				#table_name_impl
+				#connection_impl
 				#eager_load_impl
+				#existence_query_impl
 				#primary_key_impl
 				#fill_relation_impl
 			}
@@ -120,6 +125,38 @@ fn impl_eager_load(fields: &Fields) -> TokenStream {
 	}
 }
 
+fn impl_existence_query(name: &Ident, fields: &Fields, primary_key: &Field) -> TokenStream {
+	let existence_queries = fields.fields.iter().filter_map(|field| {
+		let ident = &field.ident;
+		let field_name = ident.to_string();
+		let (relationship_type, related, (_, key_expr)) = field.relationship(primary_key)?;
+
+		let relationship_ident = Ident::new(&relationship_type.to_string(), field.span());
+		let foreign_key = field.foreign_key(relationship_type, &related);
+		let local_column = if matches!(relationship_type, Relationship::BelongsTo) {
+			quote_spanned! {field.span() => &{
+				use ::ensemble::Inflector;
+				#key_expr
+			}}
+		} else {
+			quote_spanned! {field.span() => Self::PRIMARY_KEY }
+		};
+
+		Some(quote_spanned! {field.span() =>
+			#field_name => <#relationship_ident<#name, #related>>::build(#field_name, Default::default(), #foreign_key).existence_query(#local_column),
+		})
+	});
+
+	quote! {
+		fn existence_query(relation: &str) -> ::ensemble::query::Builder {
+			match relation {
+				#(#existence_queries)*
+				_ => panic!("Model does not have a {relation} relation"),
+			}
+		}
+	}
+}
+
 fn impl_fresh(primary_key: &Field) -> TokenStream {
 	let ident = &primary_key.ident;
 
@@ -198,7 +235,7 @@ fn impl_save(fields: &Fields, primary_key: &Field) -> TokenStream {
 				.await?;
 
 			if rows_affected != 1 {
-				return Err(::ensemble::Error::UniqueViolation);
+				return Err(::ensemble::Error::NotFound);
 			}
 
 			Ok(())
@@ -226,11 +263,7 @@ fn impl_create(name: &Ident, fields: &Fields, primary_key: &Field) -> TokenStrea
 	let required = fields
 		.fields
 		.iter()
-		.filter(|f| {
-			f.default(name, primary_key)
-				.map(|o| o.is_none())
-				.unwrap_or(false)
-		})
+		.filter(|f| f.default(name, primary_key).is_ok_and(|o| o.is_none()))
 		.map(|field| {
 			let ty = &field.ty;
 			let ident = &field.ident;
@@ -270,7 +303,16 @@ fn impl_create(name: &Ident, fields: &Fields, primary_key: &Field) -> TokenStrea
 	{
 		let primary_key = &primary_key.ident;
 		quote! {
-			self.#primary_key = Self::query().insert(::ensemble::value::for_db(&self)?).await?.ok_or(::ensemble::Error::Database("failed to retrieve primary key".to_string()))?;
+			let mut values = ::ensemble::value::for_db(&self)?;
+
+			// leave the key out unless it was explicitly set, so the database can generate it
+			if self.#primary_key == <Self::PrimaryKey>::default() {
+				if let ::ensemble::rbs::Value::Map(map) = &mut values {
+					map.remove(&::ensemble::rbs::Value::String(Self::PRIMARY_KEY.to_string()));
+				}
+			}
+
+			self.#primary_key = Self::query().insert(values).await?.ok_or(::ensemble::Error::MissingPrimaryKey { model: Self::NAME })?;
 
 			Ok(self)
 		}
@@ -304,6 +346,14 @@ fn impl_primary_key(primary_key: &Field) -> TokenStream {
 	}
 }
 
+fn impl_connection(connection: Option<String>) -> TokenStream {
+	connection.map_or_else(TokenStream::new, |connection| {
+		quote! {
+			const CONNECTION: &'static str = #connection;
+		}
+	})
+}
+
 fn impl_table_name(struct_name: &str, custom_name: Option<String>) -> TokenStream {
 	let table_name =
 		custom_name.unwrap_or_else(|| pluralize(&struct_name.to_snake_case(), 2, false));
@@ -18,12 +18,16 @@ pub fn impl_serialize(name: &Ident, fields: &Fields) -> syn::Result<TokenStream>
 	let primary_key = fields.primary_key()?;
 
 	let serialize_for_db = fields.fields.iter().filter_map(|field| {
+		if field.attr.aggregate {
+			return None;
+		}
+
 		let ident = &field.ident;
 		let column = field
 			.attr
 			.column
 			.as_ref()
-			.map_or(field.ident.clone(), |v| Ident::new(v, field.span()));
+			.map_or_else(|| field.ident.clone(), |v| Ident::new(v, field.span()));
 
 		let Some((relationship_type, _, (_, key_expr))) = field.relationship(primary_key) else {
 			return Some(quote_spanned! {field.span()=>
@@ -34,7 +38,7 @@ pub fn impl_serialize(name: &Ident, fields: &Fields) -> syn::Result<TokenStream>
 		match relationship_type {
 			Relationship::BelongsTo => {},
 			_ => return None,
-		};
+		}
 
 		Some(quote_spanned! {field.span()=> {
 			let key: &'static str = #key_expr.leak();
@@ -53,7 +57,7 @@ pub fn impl_serialize(name: &Ident, fields: &Fields) -> syn::Result<TokenStream>
 			.attr
 			.column
 			.as_ref()
-			.map_or(field.ident.clone(), |v| Ident::new(v, field.span()));
+			.map_or_else(|| field.ident.clone(), |v| Ident::new(v, field.span()));
 
 		Some(if field.has_relationship() {
 			quote_spanned! {field.span()=>
@@ -122,7 +126,7 @@ pub fn impl_deserialize(name: &Ident, fields: &Fields) -> syn::Result<TokenStrea
 				f.attr
 					.column
 					.as_ref()
-					.map_or(f.ident.clone(), |v| Ident::new(v, f.span())),
+					.map_or_else(|| f.ident.clone(), |v| Ident::new(v, f.span())),
 			)
 		})
 		.collect::<Rc<_>>();
@@ -219,7 +223,7 @@ fn visitor_deserialize(
             .attr
             .column
             .as_ref()
-            .map_or(f.ident.clone(), |v| Ident::new(v, f.span()));
+            .map_or_else(|| f.ident.clone(), |v| Ident::new(v, f.span()));
 
 
         if f.has_relationship() {
@@ -227,6 +231,10 @@ fn visitor_deserialize(
         }
 
         let ty = &f.ty;
+        if f.attr.aggregate {
+            return Some(quote_spanned! {f.span()=> let #ident: #ty = #ident.unwrap_or_default(); });
+        }
+
         Some(quote_spanned! {f.span()=> let #ident: #ty = #ident.ok_or_else(|| _serde::de::Error::missing_field(stringify!(#column)))?; })
     });
 
@@ -253,6 +261,7 @@ fn visitor_deserialize(
         };
 
         let relationship_ident = Ident::new(&relationship_type.to_string(), f.span());
+        let field_name = ident.to_string();
 
 
         let key_ident = key
@@ -272,7 +281,7 @@ fn visitor_deserialize(
 
         let foreign_key = f.foreign_key(*relationship_type, related);
 
-        quote_spanned! {f.span()=> #ident: <#relationship_ident<#name, #related>>::build(#key_ident.clone(), #foreign_key) }
+        quote_spanned! {f.span()=> #ident: <#relationship_ident<#name, #related>>::build(#field_name, #key_ident.clone(), #foreign_key) }
     });
 
 	let build_model = quote! {
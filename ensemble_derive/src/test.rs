@@ -0,0 +1,53 @@
+use deluxe::ParseMetaItem;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ItemFn, Path};
+
+#[derive(Debug, ParseMetaItem, Default)]
+#[deluxe(default)]
+pub struct Opts {
+	connection: Option<String>,
+	setup: Option<Path>,
+}
+
+pub fn r#impl(opts: Opts, function: ItemFn) -> syn::Result<TokenStream> {
+	let ItemFn {
+		attrs,
+		vis,
+		mut sig,
+		block,
+	} = function;
+
+	if sig.asyncness.take().is_none() {
+		return Err(syn::Error::new_spanned(
+			sig.fn_token,
+			"#[ensemble::test] functions must be async",
+		));
+	}
+
+	if !sig.inputs.is_empty() {
+		return Err(syn::Error::new_spanned(
+			&sig.inputs,
+			"#[ensemble::test] functions can't take arguments",
+		));
+	}
+
+	let setup = opts.setup.map_or_else(
+		|| quote! { ::ensemble::testing::setup_from_env() },
+		|setup| quote! { #setup() },
+	);
+
+	let connection = opts.connection.unwrap_or_else(|| "default".to_string());
+
+	Ok(quote! {
+		#[::core::prelude::v1::test]
+		#(#attrs)*
+		#vis #sig {
+			::ensemble::testing::run(async move {
+				#setup.await;
+
+				::ensemble::testing::isolated_on(#connection, || async move #block).await
+			})
+		}
+	})
+}
@@ -92,3 +92,26 @@ fn hidden_fields_hidden_when_serializing_to_json() {
 
     assert_eq!(serde_json::to_value(model).unwrap(), json!({ "id": 123 }));
 }
+
+#[test]
+fn aggregate_fields_are_only_serialized_to_json() {
+    #[derive(Debug, Model)]
+    struct MyModel {
+        id: u8,
+        #[model(aggregate)]
+        comments_count: u64,
+    }
+
+    let model = MyModel {
+        id: 123,
+        comments_count: 4,
+    };
+
+    assert_eq!(model.json(), json!({ "id": 123, "comments_count": 4 }));
+    assert_eq!(
+        to_value!(model),
+        rbs::Value::Map(value_map! {
+            "id" : 123u32,
+        })
+    );
+}
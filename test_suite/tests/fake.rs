@@ -0,0 +1,3 @@
+mod fake {
+	automod::dir!("tests/fake");
+}
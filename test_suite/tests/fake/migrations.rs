@@ -0,0 +1,75 @@
+use ensemble::{
+    migrations::{Error, Migration, Migrator, Schema},
+    rbs::Value,
+    testing::{self, FakeConnection},
+};
+use serde_json::json;
+
+#[derive(Debug, Default)]
+struct CreateTagsTable;
+
+#[ensemble::async_trait]
+impl Migration for CreateTagsTable {
+    async fn up(&self) -> Result<(), Error> {
+        Schema::create("tags", |table| {
+            table.id();
+            table.string("name");
+        })
+        .await
+    }
+
+    async fn down(&self) -> Result<(), Error> {
+        Schema::drop("tags").await
+    }
+}
+
+#[test]
+fn runs_migrations_in_transactions() {
+    let fake = FakeConnection::sqlite();
+
+    testing::run(fake.scope(async {
+        let mut migrator = Migrator::new().await.unwrap();
+        migrator.register("CreateTagsTable".to_string(), Box::new(CreateTagsTable));
+        migrator.run().await.unwrap();
+    }));
+
+    let sql = fake.sql();
+    assert_eq!(sql[1], "select * from migrations");
+    assert_eq!(sql[2], "BEGIN");
+    assert!(sql[3].starts_with("CREATE TABLE tags"));
+    assert_eq!(
+        sql[4..],
+        [
+            "insert into migrations (migration, batch) values (?, ?)",
+            "COMMIT"
+        ]
+    );
+    assert_eq!(
+        fake.queries()[4].bindings,
+        [Value::String("CreateTagsTable".to_string()), Value::U64(1)]
+    );
+}
+
+#[test]
+fn rolls_back_migrations() {
+    let fake = FakeConnection::sqlite();
+    fake.push_result(0, Value::Null);
+    fake.push_rows([json!({ "id": 3, "batch": 1, "migration": "CreateTagsTable" })]);
+
+    testing::run(fake.scope(async {
+        let mut migrator = Migrator::new().await.unwrap();
+        migrator.register("CreateTagsTable".to_string(), Box::new(CreateTagsTable));
+        migrator.rollback(1).await.unwrap();
+    }));
+
+    assert_eq!(
+        fake.sql()[2..],
+        [
+            "BEGIN",
+            "DROP TABLE tags",
+            "delete from migrations where id = ?",
+            "COMMIT"
+        ]
+    );
+    assert_eq!(fake.queries()[4].bindings, [Value::U64(3)]);
+}
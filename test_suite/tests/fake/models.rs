@@ -0,0 +1,288 @@
+use ensemble::{
+    rbs::Value,
+    relationships::{BelongsTo, HasMany, Relationship},
+    testing::{self, FakeConnection},
+    Error, Model,
+};
+use serde_json::json;
+
+#[derive(Debug, Clone, Model)]
+struct User {
+    id: u64,
+    name: String,
+    posts: HasMany<User, Post>,
+    #[model(aggregate)]
+    posts_count: u64,
+}
+
+#[derive(Debug, Clone, Model)]
+struct Post {
+    id: u64,
+    title: String,
+    user: BelongsTo<Post, User>,
+}
+
+#[test]
+fn creates_models() {
+    let fake = FakeConnection::postgres();
+    fake.push_result(1, 7u64);
+
+    let user = testing::run(fake.scope(
+        User {
+            name: "Alice".to_string(),
+            ..User::default()
+        }
+        .create(),
+    ))
+    .unwrap();
+
+    assert_eq!(user.id, 7);
+    assert_eq!(fake.sql(), [r#"INSERT INTO users ("name") VALUES (?)"#]);
+    assert_eq!(
+        fake.queries()[0].bindings,
+        [Value::String("Alice".to_string())]
+    );
+}
+
+#[test]
+fn keeps_explicitly_set_incrementing_keys() {
+    let fake = FakeConnection::postgres();
+    fake.push_result(1, 42u64);
+
+    let user = testing::run(fake.scope(
+        User {
+            id: 42,
+            name: "Alice".to_string(),
+            ..User::default()
+        }
+        .create(),
+    ))
+    .unwrap();
+
+    assert_eq!(user.id, 42);
+    assert_eq!(
+        fake.sql(),
+        [r#"INSERT INTO users ("id", "name") VALUES (?, ?)"#]
+    );
+}
+
+#[test]
+fn saves_and_deletes_models() {
+    let fake = FakeConnection::mysql();
+    fake.push_result(1, Value::Null);
+    fake.push_result(0, Value::Null);
+
+    let mut user = User {
+        id: 1,
+        name: "Alice".to_string(),
+        ..User::default()
+    };
+
+    testing::run(fake.scope(async {
+        user.save().await.unwrap();
+
+        assert!(matches!(user.delete().await, Err(Error::NotFound)));
+    }));
+
+    assert_eq!(
+        fake.sql(),
+        [
+            "UPDATE users SET `id` = ?, `name` = ? WHERE `id` = ?",
+            "DELETE FROM users WHERE `id` = ?",
+        ]
+    );
+}
+
+#[test]
+fn eager_loads_relationships() {
+    let fake = FakeConnection::sqlite();
+    fake.push_rows([json!({ "id": 1, "name": "Alice" })]);
+    fake.push_rows([json!({ "id": 2, "title": "Hello", "user_id": 1 })]);
+
+    let users = testing::run(fake.scope(User::with("posts").get::<User>())).unwrap();
+
+    assert_eq!(users[0].posts.peek().unwrap()[0].title, "Hello");
+    assert_eq!(
+        fake.sql(),
+        [
+            "SELECT * FROM users",
+            r#"SELECT * FROM posts WHERE "posts"."user_id" IN (?) AND "posts"."user_id" IS NOT NULL  "#,
+        ]
+    );
+    assert_eq!(fake.queries()[1].bindings, [Value::U64(1)]);
+}
+
+#[test]
+fn filters_by_relationship_existence() {
+    let fake = FakeConnection::sqlite();
+
+    testing::run(fake.scope(async {
+        User::query()
+            .where_has("posts", |query| query.r#where("title", "like", "Hello%"))
+            .has("posts", ">=", 3)
+            .get::<User>()
+            .await
+            .unwrap();
+
+        Post::query().doesnt_have("user").get::<Post>().await.unwrap();
+    }));
+
+    assert_eq!(
+        fake.sql(),
+        [
+            r#"SELECT * FROM users WHERE EXISTS (SELECT * FROM posts WHERE "posts"."user_id" = "users"."id" AND "title" LIKE ? ) AND (SELECT COUNT(*) FROM posts WHERE "posts"."user_id" = "users"."id") >= ? "#,
+            r#"SELECT * FROM posts WHERE NOT EXISTS (SELECT * FROM users WHERE "users"."id" = "posts"."user_id")"#,
+        ]
+    );
+    assert_eq!(
+        fake.queries()[0].bindings,
+        [Value::String("Hello%".to_string()), Value::U64(3)]
+    );
+}
+
+#[test]
+fn selects_relationship_aggregates() {
+    let fake = FakeConnection::postgres();
+    fake.push_rows([json!({ "id": 1, "name": "Alice", "posts_count": 2 })]);
+
+    let users = testing::run(fake.scope(
+        User::query()
+            .with_count("posts")
+            .with_max("posts", "id")
+            .with_exists("posts")
+            .get::<User>(),
+    ))
+    .unwrap();
+
+    assert_eq!(users[0].posts_count, 2);
+    assert_eq!(users[0].json(), json!({ "id": 1, "name": "Alice", "posts_count": 2 }));
+    assert_eq!(
+        fake.sql(),
+        [
+            r#"SELECT "users".*, (SELECT COUNT(*) FROM posts WHERE "posts"."user_id" = "users"."id") AS "posts_count", (SELECT MAX("id") AS aggregate FROM posts WHERE "posts"."user_id" = "users"."id") AS "posts_max_id", (EXISTS (SELECT * FROM posts WHERE "posts"."user_id" = "users"."id")) AS "posts_exists" FROM users"#,
+        ]
+    );
+}
+
+#[test]
+fn paginates_without_ordering_the_count() {
+    let fake = FakeConnection::sqlite();
+    fake.push_rows([json!({ "count": 3 })]);
+    fake.push_rows([json!({ "id": 3, "name": "Carol" })]);
+
+    let page = testing::run(fake.scope(
+        User::query()
+            .order_by("name", "asc")
+            .paginate::<User>(2, 2),
+    ))
+    .unwrap();
+
+    assert_eq!((page.total, page.last_page), (3, 2));
+    assert_eq!(page.items[0].name, "Carol");
+    assert_eq!(
+        fake.sql(),
+        [
+            "SELECT COUNT(*) FROM users",
+            r#"SELECT * FROM users ORDER BY "name" ASC LIMIT 2 OFFSET 2"#,
+        ]
+    );
+}
+
+#[test]
+fn cursor_paginates_after_or_conditions() {
+    let fake = FakeConnection::sqlite();
+    fake.push_rows([json!({ "id": 1, "name": "Alice" }), json!({ "id": 2, "name": "Bob" })]);
+
+    let query = || {
+        User::query()
+            .r#where("name", "=", "Alice")
+            .or_where("name", "=", "Bob")
+    };
+
+    let first = testing::run(fake.scope(query().cursor_paginate::<User>(1, None))).unwrap();
+    testing::run(fake.scope(query().cursor_paginate::<User>(1, first.next_cursor))).unwrap();
+
+    assert_eq!(
+        fake.sql()[1],
+        r#"SELECT * FROM users WHERE ("name" = ? OR "name" = ? ) AND "id" > ?  ORDER BY "id" ASC LIMIT 2"#
+    );
+    assert_eq!(
+        fake.queries()[1].bindings,
+        [
+            Value::String("Alice".to_string()),
+            Value::String("Bob".to_string()),
+            Value::U64(1)
+        ]
+    );
+    assert!(matches!(
+        testing::run(fake.scope(query().cursor_paginate::<User>(u64::MAX, None))),
+        Err(Error::InvalidQuery)
+    ));
+}
+
+#[test]
+fn chunks_by_id_and_eager_loads_once_per_chunk() {
+    let fake = FakeConnection::sqlite();
+    fake.push_rows([json!({ "id": 1, "name": "Alice" }), json!({ "id": 4, "name": "Bob" })]);
+    fake.push_rows([json!({ "id": 2, "title": "Hello", "user_id": 4 })]);
+    fake.push_rows([json!({ "id": 7, "name": "Bob" })]);
+
+    let mut chunks = vec![];
+    let completed = testing::run(fake.scope(
+        User::with("posts")
+            .r#where("name", "=", "Alice")
+            .or_where("name", "=", "Bob")
+            .chunk_by_id(2, |users: Vec<User>| {
+                chunks.push(users.iter().map(|user| user.id).collect::<Vec<_>>());
+                async { Ok(true) }
+            }),
+    ))
+    .unwrap();
+
+    assert!(completed);
+    assert_eq!(chunks, [vec![1, 4], vec![7]]);
+    assert_eq!(
+        fake.sql(),
+        [
+            r#"SELECT * FROM users WHERE ("name" = ? OR "name" = ? ) ORDER BY "users"."id" ASC LIMIT 2"#,
+            r#"SELECT * FROM posts WHERE "posts"."user_id" IN (?, ?) AND "posts"."user_id" IS NOT NULL  "#,
+            r#"SELECT * FROM users WHERE ("name" = ? OR "name" = ? ) AND "users"."id" > ?  ORDER BY "users"."id" ASC LIMIT 2"#,
+            r#"SELECT * FROM posts WHERE "posts"."user_id" IN (?) AND "posts"."user_id" IS NOT NULL  "#,
+        ]
+    );
+    assert_eq!(fake.queries()[2].bindings[2], Value::U64(4));
+}
+
+#[test]
+fn surfaces_database_errors() {
+    let fake = FakeConnection::sqlite();
+    fake.push_error("UNIQUE constraint failed: users.name");
+
+    let error = testing::run(fake.scope(
+        User {
+            name: "Alice".to_string(),
+            ..User::default()
+        }
+        .create(),
+    ))
+    .unwrap_err();
+
+    assert!(matches!(error, Error::UniqueViolation { columns, .. } if columns == ["name"]));
+}
+
+#[test]
+fn reports_a_missing_primary_key() {
+    let fake = FakeConnection::postgres();
+    fake.push_result(1, Value::Null);
+
+    let error = testing::run(fake.scope(
+        User {
+            name: "Alice".to_string(),
+            ..User::default()
+        }
+        .create(),
+    ))
+    .unwrap_err();
+
+    assert!(matches!(error, Error::MissingPrimaryKey { model: "User" }));
+}
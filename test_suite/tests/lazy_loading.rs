@@ -0,0 +1,104 @@
+//! Lazy loading is disabled globally, so these tests run in their own binary.
+
+use ensemble::{
+	relationships::{HasMany, Relationship},
+	testing::{self, FakeConnection},
+	Error, Model,
+};
+use serde_json::json;
+use std::{panic::AssertUnwindSafe, sync::Mutex};
+
+/// The tests change the same global setting, so they take turns.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Model)]
+struct User {
+	id: u64,
+	name: String,
+	posts: HasMany<User, Post>,
+}
+
+#[derive(Debug, Clone, Model)]
+struct Post {
+	id: u64,
+	title: String,
+	user_id: u64,
+}
+
+/// Lazy loads the posts of a user, catching the panic if it panics.
+fn lazy_load_posts() -> std::thread::Result<Result<(), Error>> {
+	let fake = FakeConnection::sqlite();
+	fake.push_rows([json!({ "id": 1, "name": "Alice" })]);
+
+	std::panic::catch_unwind(AssertUnwindSafe(|| {
+		testing::run(fake.scope(async {
+			let mut user = User::find(1).await.unwrap();
+			user.posts.get().await.map(|_| ())
+		}))
+	}))
+}
+
+fn is_violation_panic(panic: &(dyn std::any::Any + Send)) -> bool {
+	panic.downcast_ref::<String>().is_some_and(|message| {
+		message.starts_with("Attempted to lazy load [posts] on model [User]")
+	})
+}
+
+#[test]
+fn prevents_lazy_loading() {
+	let _guard = LOCK.lock().unwrap();
+
+	ensemble::prevent_lazy_loading(true);
+	let result = lazy_load_posts();
+	if cfg!(debug_assertions) {
+		assert!(is_violation_panic(&*result.unwrap_err()));
+	} else {
+		assert!(matches!(
+			result.unwrap(),
+			Err(Error::LazyLoadingViolation {
+				model: "User",
+				relation: "posts"
+			})
+		));
+	}
+
+	ensemble::prevent_lazy_loading_with(false);
+	assert!(matches!(
+		lazy_load_posts().unwrap(),
+		Err(Error::LazyLoadingViolation {
+			model: "User",
+			relation: "posts"
+		})
+	));
+
+	ensemble::prevent_lazy_loading_with(true);
+	assert!(is_violation_panic(&*lazy_load_posts().unwrap_err()));
+}
+
+#[test]
+fn allows_eager_loaded_relationships() {
+	let _guard = LOCK.lock().unwrap();
+	ensemble::prevent_lazy_loading(true);
+
+	let fake = FakeConnection::sqlite();
+	fake.push_rows([
+		json!({ "id": 1, "name": "Alice" }),
+		json!({ "id": 2, "name": "Bob" }),
+	]);
+	fake.push_rows([json!({ "id": 3, "title": "Hello", "user_id": 1 })]);
+
+	let mut users = testing::run(fake.scope(async {
+		let mut users = User::with("posts").get::<User>().await.unwrap();
+
+		for user in &mut users {
+			user.posts.get().await.unwrap();
+		}
+
+		users
+	}));
+
+	assert_eq!(users[0].posts.peek().unwrap()[0].title, "Hello");
+	assert!(users[1].posts.peek().unwrap().is_empty());
+	assert_eq!(fake.sql().len(), 2);
+	assert!(testing::run(users[1].posts.get()).unwrap().is_empty());
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "sqlite")]
+
+use ensemble::migrations::{Error, Migration, Migrator, Schema};
+use std::{env, future::Future, process};
+use tokio::sync::OnceCell;
+
+mod sqlite {
+	automod::dir!("tests/sqlite");
+}
+
+/// Runs the given future against an in-memory SQLite database, setting it up first if needed.
+///
+/// The in-memory database only lives as long as its connections, so every test shares a single runtime (and pool).
+pub fn run<F: Future>(test: F) -> F::Output {
+	ensemble::testing::run(async {
+		setup().await;
+		test.await
+	})
+}
+
+/// Sets up the database pools and runs the test migrations, the first time it's called.
+pub async fn setup() {
+	static SETUP: OnceCell<()> = OnceCell::const_new();
+
+	SETUP
+		.get_or_init(|| async {
+			ensemble::setup("sqlite::memory:").expect("Failed to set up database pool.");
+
+			ensemble::migrate!(CreateUsersTable, CreatePostsTable, CreateCategoriesTable)
+				.await
+				.expect("Failed to run migrations.");
+
+			ensemble::setup_named("analytics", "sqlite::memory:")
+				.expect("Failed to set up analytics database pool.");
+
+			// the replica is a separate (and empty) in-memory database, so tests can tell where each query went.
+			ensemble::setup_named_with_replicas(
+				"replicated",
+				"sqlite::memory:",
+				&["sqlite::memory:"],
+			)
+			.expect("Failed to set up replicated database pool.");
+
+			ensemble::setup_named("transactional", "sqlite::memory:")
+				.expect("Failed to set up transactional database pool.");
+
+			// in-memory databases fail instead of waiting when another connection holds a lock,
+			// so the tests that run in parallel transactions use a database file instead.
+			let path = env::temp_dir().join(format!("ensemble-test-suite-{}.db", process::id()));
+			ensemble::setup_named("isolated", &format!("sqlite://{}", path.display()))
+				.expect("Failed to set up isolated database pool.");
+
+			for connection in ["analytics", "replicated", "transactional", "isolated"] {
+				let mut migrator = Migrator::on(connection)
+					.await
+					.expect("Failed to connect to database.");
+				migrator.register("CreateEventsTable".to_string(), Box::new(CreateEventsTable));
+				migrator.run().await.expect("Failed to run migrations.");
+			}
+		})
+		.await;
+}
+
+#[derive(Debug, Default)]
+pub struct CreateUsersTable;
+
+#[ensemble::async_trait]
+impl Migration for CreateUsersTable {
+	async fn up(&self) -> Result<(), Error> {
+		Schema::create("users", |table| {
+			table.id();
+			table.string("name");
+			table.string("email").unique(true);
+			table.boolean("is_admin").default(false);
+			table.timestamps();
+		})
+		.await
+	}
+
+	async fn down(&self) -> Result<(), Error> {
+		Schema::drop("users").await
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct CreatePostsTable;
+
+#[ensemble::async_trait]
+impl Migration for CreatePostsTable {
+	async fn up(&self) -> Result<(), Error> {
+		Schema::create("posts", |table| {
+			table.id();
+			table.string("title");
+			table.text("content");
+			table.timestamps();
+
+			table.foreign_id("user_id").on_delete("cascade");
+		})
+		.await
+	}
+
+	async fn down(&self) -> Result<(), Error> {
+		Schema::drop("posts").await
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct CreateCategoriesTable;
+
+#[ensemble::async_trait]
+impl Migration for CreateCategoriesTable {
+	async fn up(&self) -> Result<(), Error> {
+		Schema::create("categories", |table| {
+			table.id();
+			table.string("name");
+			table.integer("parent_id").nullable(true);
+		})
+		.await
+	}
+
+	async fn down(&self) -> Result<(), Error> {
+		Schema::drop("categories").await
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct CreateEventsTable;
+
+#[ensemble::async_trait]
+impl Migration for CreateEventsTable {
+	async fn up(&self) -> Result<(), Error> {
+		Schema::create("events", |table| {
+			table.id();
+			table.string("name");
+			table.timestamps();
+		})
+		.await
+	}
+
+	async fn down(&self) -> Result<(), Error> {
+		Schema::drop("events").await
+	}
+}
@@ -0,0 +1,51 @@
+use ensemble::{query::Builder, types::DateTime, Model};
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+#[ensemble(connection = "analytics")]
+struct Event {
+    id: u64,
+    name: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+#[test]
+fn models_use_their_named_connection() {
+    run(async {
+        let event = Event::create(Event {
+            name: "signup".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(Event::find(event.id).await.unwrap().name, "signup");
+        assert!(Event::query().on("default").count().await.is_err());
+    });
+}
+
+#[test]
+fn queries_fail_on_unknown_connections() {
+    run(async {
+        let err = Event::query().on("missing").count().await.unwrap_err();
+
+        assert!(matches!(err, ensemble::Error::Connection(_)));
+    });
+}
+
+#[test]
+fn raw_sql_runs_on_the_named_connection() {
+    run(async {
+        let sql = "select count(*) as total from events";
+
+        let rows = unsafe { Builder::raw_sql_on("analytics", sql, vec![]) }
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+
+        // the events table only exists on the named connections
+        assert!(unsafe { Builder::raw_sql(sql, vec![]) }.await.is_err());
+    });
+}
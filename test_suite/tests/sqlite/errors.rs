@@ -0,0 +1,68 @@
+use ensemble::{rbs::Value, types::DateTime, Error, Model};
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+struct User {
+    id: u64,
+    name: String,
+    email: String,
+    #[model(default = false)]
+    is_admin: bool,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+fn user(email: &str) -> User {
+    User {
+        name: "Alice".to_string(),
+        email: email.to_string(),
+        ..User::default()
+    }
+}
+
+#[test]
+fn classifies_unique_violations() {
+    run(async {
+        user("duplicate@example.com").create().await.unwrap();
+
+        let error = user("duplicate@example.com").create().await.unwrap_err();
+
+        let Error::UniqueViolation {
+            columns, details, ..
+        } = error
+        else {
+            panic!("expected a unique violation, got {error:?}");
+        };
+        assert_eq!(columns, ["email"]);
+        assert_eq!(details.sqlstate.as_deref(), Some("23505"));
+        assert!(details.sql.starts_with("INSERT INTO"));
+        assert!(details
+            .bindings
+            .contains(&Value::String("duplicate@example.com".to_string())));
+    });
+}
+
+#[test]
+fn classifies_not_null_violations() {
+    run(async {
+        let error = User::query()
+            .insert::<u64, _>(vec![("email", "nameless@example.com")])
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(error, Error::NotNullViolation { column: Some(ref column), .. } if column == "name")
+        );
+    });
+}
+
+#[test]
+fn saving_a_missing_model_is_not_a_unique_violation() {
+    run(async {
+        let mut user = user("missing@example.com");
+        user.id = u64::MAX / 2;
+
+        assert!(matches!(user.save().await.unwrap_err(), Error::NotFound));
+    });
+}
@@ -0,0 +1,60 @@
+use ensemble::{rbs::Value, types::DateTime, Model, QueryEvent};
+use std::{sync::Mutex, time::Duration};
+
+use crate::run;
+
+static EVENTS: Mutex<Vec<QueryEvent>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Model)]
+#[ensemble(connection = "analytics")]
+struct Event {
+    id: u64,
+    name: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+/// Returns the recorded queries bound to the given value, since other tests run queries at the same time.
+fn events_for(value: &str) -> Vec<QueryEvent> {
+    EVENTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| event.bindings.contains(&Value::String(value.to_string())))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn listeners_receive_every_query() {
+    run(async {
+        ensemble::listen(|event| EVENTS.lock().unwrap().push(event.clone()));
+        ensemble::log_slow_queries(Duration::ZERO);
+
+        Event::create(Event {
+            name: "listened".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let events = events_for("listened");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].sql.starts_with("INSERT INTO events"));
+        assert_eq!(events[0].connection, "analytics");
+        assert_eq!(events[0].rows, 1);
+        assert!(events[0].error.is_none());
+
+        let result = Event::query()
+            .from("missing_events")
+            .r#where("name", "=", "failed")
+            .count()
+            .await;
+        assert!(result.is_err());
+
+        let events = events_for("failed");
+        assert_eq!(events.len(), 1);
+        assert!(events[0].sql.starts_with("SELECT COUNT(*) FROM missing_events"));
+        assert!(events[0].error.is_some());
+    });
+}
@@ -0,0 +1,67 @@
+use ensemble::{types::DateTime, Model};
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+#[ensemble(connection = "isolated")]
+struct Event {
+    id: u64,
+    name: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+async fn count(name: &str) -> u64 {
+    Event::query()
+        .r#where("name", "=", name)
+        .count()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn isolated_closures_are_rolled_back() {
+    run(async {
+        ensemble::testing::isolated_on("isolated", || async {
+            Event {
+                name: "rolled-back".to_string(),
+                ..Event::default()
+            }
+            .create()
+            .await
+            .unwrap();
+
+            assert_eq!(count("rolled-back").await, 1);
+        })
+        .await;
+
+        assert_eq!(count("rolled-back").await, 0);
+    });
+}
+
+#[ensemble::test(setup = crate::setup, connection = "isolated")]
+async fn isolated_tests_only_see_their_own_rows() {
+    Event {
+        name: "isolated".to_string(),
+        ..Event::default()
+    }
+    .create()
+    .await
+    .unwrap();
+
+    assert_eq!(count("isolated").await, 1);
+}
+
+#[ensemble::test(setup = crate::setup, connection = "isolated")]
+async fn isolated_tests_can_run_in_parallel() -> Result<(), ensemble::Error> {
+    Event {
+        name: "isolated".to_string(),
+        ..Event::default()
+    }
+    .create()
+    .await?;
+
+    assert_eq!(count("isolated").await, 1);
+
+    Ok(())
+}
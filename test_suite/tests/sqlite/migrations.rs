@@ -0,0 +1,90 @@
+use ensemble::migrations::{Error, Migration, Migrator, Schema};
+
+use crate::run;
+
+#[derive(Debug, Default)]
+struct CreateCommentsTable;
+
+#[ensemble::async_trait]
+impl Migration for CreateCommentsTable {
+    async fn up(&self) -> Result<(), Error> {
+        Schema::create("comments", |table| {
+            table.uuid();
+            table.json("metadata").nullable(true);
+            table.foreign_id("post_id").on_delete("cascade");
+            table.timestamps();
+        })
+        .await
+    }
+
+    async fn down(&self) -> Result<(), Error> {
+        Schema::drop("comments").await
+    }
+}
+
+#[test]
+fn runs_and_rolls_back_migrations() {
+    run(async {
+        let mut migrator = Migrator::new().await.unwrap();
+        migrator.register(
+            "CreateCommentsTable".to_string(),
+            Box::new(CreateCommentsTable),
+        );
+        assert_eq!(migrator.pending().len(), 1);
+        migrator.run().await.unwrap();
+
+        let mut migrator = Migrator::new().await.unwrap();
+        migrator.register(
+            "CreateCommentsTable".to_string(),
+            Box::new(CreateCommentsTable),
+        );
+        assert!(migrator.pending().is_empty());
+        assert!(migrator
+            .status()
+            .iter()
+            .any(|m| m.migration == "CreateCommentsTable"));
+        migrator.rollback(1).await.unwrap();
+
+        let migrator = Migrator::new().await.unwrap();
+        assert!(!migrator
+            .status()
+            .iter()
+            .any(|m| m.migration == "CreateCommentsTable"));
+    });
+}
+
+#[derive(Debug, Default)]
+struct RecreateUsersTable;
+
+#[ensemble::async_trait]
+impl Migration for RecreateUsersTable {
+    async fn up(&self) -> Result<(), Error> {
+        Schema::create("users", |table| {
+            table.id();
+        })
+        .await
+    }
+
+    async fn down(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn classifies_errors_from_schema_changes() {
+    run(async {
+        let mut migrator = Migrator::new().await.unwrap();
+        migrator.register(
+            "RecreateUsersTable".to_string(),
+            Box::new(RecreateUsersTable),
+        );
+
+        let error = migrator.run().await.unwrap_err();
+
+        let Error::Query(ensemble::Error::Query { details }) = error else {
+            panic!("expected a classified query error, got {error:?}");
+        };
+        assert!(details.message.contains("already exists"));
+        assert!(details.sql.starts_with("CREATE TABLE"));
+    });
+}
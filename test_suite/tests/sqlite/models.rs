@@ -0,0 +1,124 @@
+use ensemble::{
+    relationships::{BelongsTo, HasMany, Relationship},
+    types::DateTime,
+    Model,
+};
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+struct User {
+    id: u64,
+    name: String,
+    email: String,
+    #[model(default = false)]
+    is_admin: bool,
+    created_at: DateTime,
+    updated_at: DateTime,
+    posts: HasMany<User, Post>,
+}
+
+#[derive(Debug, Clone, Model)]
+struct Post {
+    id: u64,
+    title: String,
+    content: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+    user: BelongsTo<Post, User>,
+}
+
+fn user(email: &str) -> User {
+    User {
+        name: "Alice".to_string(),
+        email: email.to_string(),
+        ..User::default()
+    }
+}
+
+#[test]
+fn creates_and_finds_models() {
+    run(async {
+        let created = user("create@example.com").create().await.unwrap();
+        assert_ne!(created.id, 0);
+
+        let found = User::find(created.id).await.unwrap();
+        assert_eq!(found.email, "create@example.com");
+        assert!(!found.is_admin);
+    });
+}
+
+#[test]
+fn updates_and_deletes_models() {
+    run(async {
+        let mut user = user("update@example.com").create().await.unwrap();
+        user.is_admin = true;
+        user.save().await.unwrap();
+
+        assert!(user.fresh().await.unwrap().is_admin);
+
+        let id = user.id;
+        user.delete().await.unwrap();
+
+        assert!(matches!(
+            User::find(id).await.unwrap_err(),
+            ensemble::Error::NotFound
+        ));
+    });
+}
+
+#[test]
+fn queries_and_counts_models() {
+    run(async {
+        user("count-1@example.com").create().await.unwrap();
+        user("count-2@example.com").create().await.unwrap();
+
+        let count = User::query()
+            .r#where("email", "like", "count-%")
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let users: Vec<User> = User::query()
+            .r#where("email", "like", "count-%")
+            .order_by("email", "desc")
+            .get()
+            .await
+            .unwrap();
+        assert_eq!(users[0].email, "count-2@example.com");
+    });
+}
+
+#[test]
+fn loads_relationships() {
+    run(async {
+        let user = user("relationships@example.com").create().await.unwrap();
+        let mut user = user.fresh().await.unwrap();
+        user.posts
+            .create(Post {
+                title: "Hello".to_string(),
+                content: "World".to_string(),
+                ..Post::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(user.posts().await.unwrap().len(), 1);
+
+        let users = User::with("posts")
+            .r#where("id", "=", user.id)
+            .get::<User>()
+            .await
+            .unwrap();
+        assert_eq!(users[0].posts.peek().unwrap()[0].title, "Hello");
+
+        let mut post = Post::query()
+            .r#where("user_id", "=", user.id)
+            .first::<Post>()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(post.user().await.unwrap().id, user.id);
+    });
+}
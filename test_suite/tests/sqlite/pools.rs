@@ -0,0 +1,88 @@
+use ensemble::{SetupOptions, TlsMode};
+use std::time::Duration;
+
+use crate::run;
+
+#[test]
+fn eager_pools_open_their_idle_connections() {
+    run(async {
+        ensemble::setup_named_with(
+            "pooled",
+            SetupOptions::new("sqlite::memory:")
+                .max_connections(4)
+                .max_idle_connections(2)
+                .tls(TlsMode::Require)
+                .lazy(false),
+        )
+        .await
+        .unwrap();
+
+        let stats = ensemble::pool_stats("pooled").await.unwrap();
+        assert_eq!(stats.max_connections, 4);
+        assert_eq!(stats.wait_count, 0);
+        assert!(stats.connections >= 2);
+
+        ensemble::transaction_on("pooled", || async {
+            let stats = ensemble::pool_stats("pooled").await?;
+            assert!(stats.in_use >= 1);
+
+            Ok::<_, ensemble::Error>(())
+        })
+        .await
+        .unwrap();
+    });
+}
+
+#[test]
+fn zero_timeouts_disable_expiry() {
+    run(async {
+        ensemble::setup_named_with(
+            "unexpiring",
+            SetupOptions::new("sqlite::memory:")
+                .idle_timeout(Duration::ZERO)
+                .max_lifetime(Duration::ZERO)
+                .lazy(false),
+        )
+        .await
+        .unwrap();
+
+        assert!(ensemble::pool_stats("unexpiring").await.unwrap().connections >= 1);
+    });
+}
+
+#[test]
+fn eager_pools_fail_when_the_database_is_unreachable() {
+    run(async {
+        let options = SetupOptions::new("sqlite:///missing/directory/app.db").lazy(false);
+
+        assert!(matches!(
+            ensemble::setup_named_with("unreachable", options).await,
+            Err(ensemble::SetupError::Connection(_))
+        ));
+        assert!(ensemble::pool_stats("unreachable").await.is_err());
+    });
+}
+
+#[test]
+fn lazy_pools_open_their_minimum_connections() {
+    run(async {
+        ensemble::setup_named_with(
+            "warm",
+            SetupOptions::new("sqlite::memory:")
+                .max_connections(5)
+                .min_connections(3),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(ensemble::pool_stats("warm").await.unwrap().connections, 3);
+
+        let options = SetupOptions::new("sqlite::memory:")
+            .min_connections(3)
+            .idle_timeout(Duration::from_secs(30));
+        assert!(matches!(
+            ensemble::setup_named_with("expiring", options).await,
+            Err(ensemble::SetupError::InvalidOptions(_))
+        ));
+    });
+}
@@ -0,0 +1,839 @@
+use ensemble::{rbs::Value, relationships::HasMany, types::DateTime, Model};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+struct User {
+    id: u64,
+    name: String,
+    email: String,
+    #[model(default = false)]
+    is_admin: bool,
+    created_at: DateTime,
+    updated_at: DateTime,
+    posts: HasMany<User, Post>,
+    #[model(aggregate)]
+    posts_count: u64,
+    #[model(aggregate)]
+    posts_max_title: Option<String>,
+    #[model(aggregate)]
+    posts_exists: bool,
+}
+
+#[derive(Debug, Clone, Model)]
+struct Post {
+    id: u64,
+    title: String,
+    content: String,
+    user_id: u64,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+async fn create_users(prefix: &str, names: &[&str]) {
+    for (i, name) in names.iter().enumerate() {
+        User {
+            name: (*name).to_string(),
+            email: format!("{prefix}-{i}@example.com"),
+            ..User::default()
+        }
+        .create()
+        .await
+        .unwrap();
+    }
+}
+
+#[test]
+fn selects_columns() {
+    run(async {
+        create_users("select", &["Alice", "Bob"]).await;
+
+        let users = User::query()
+            .select(&["id", "email"])
+            .r#where("email", "like", "select-%")
+            .order_by("email", "asc")
+            .get::<User>()
+            .await
+            .unwrap();
+
+        assert_eq!(users.len(), 2);
+        assert_ne!(users[0].id, 0);
+        assert_eq!(users[0].email, "select-0@example.com");
+        assert_eq!(users[0].name, "");
+
+        let users = User::query()
+            .select(&["id"])
+            .select_raw("? || email AS email", vec![Value::from("copy-")])
+            .r#where("email", "=", "select-1@example.com")
+            .get::<User>()
+            .await
+            .unwrap();
+
+        assert_eq!(users[0].email, "copy-select-1@example.com");
+    });
+}
+
+#[test]
+fn selects_distinct_rows() {
+    run(async {
+        create_users("distinct", &["Alice", "Alice", "Bob"]).await;
+
+        let query = || {
+            User::query()
+                .select(&["name"])
+                .distinct()
+                .r#where("email", "like", "distinct-%")
+        };
+
+        assert_eq!(query().count().await.unwrap(), 2);
+        assert_eq!(query().get::<User>().await.unwrap().len(), 2);
+    });
+}
+
+#[test]
+fn calculates_aggregates() {
+    run(async {
+        create_users("aggregate", &["Alice", "Bob", "Carol"]).await;
+
+        let query = || User::query().r#where("email", "like", "aggregate-%");
+        let min: u64 = query().min("id").await.unwrap().unwrap();
+        let max: u64 = query().max("id").await.unwrap().unwrap();
+
+        assert_eq!(max - min, 2);
+        assert_eq!(query().sum::<u64>("id").await.unwrap(), Some(min * 3 + 3));
+        assert_eq!(
+            query().avg::<f64>("id").await.unwrap(),
+            Some(min as f64 + 1.0)
+        );
+        assert_eq!(
+            query().max::<String>("name").await.unwrap().as_deref(),
+            Some("Carol")
+        );
+        assert!(query()
+            .max::<DateTime>("created_at")
+            .await
+            .unwrap()
+            .is_some());
+
+        let missing = || User::query().r#where("email", "=", "aggregate-missing@example.com");
+        assert_eq!(missing().sum::<u64>("id").await.unwrap(), None);
+        assert_eq!(missing().avg::<f64>("id").await.unwrap(), None);
+        assert_eq!(missing().max::<DateTime>("created_at").await.unwrap(), None);
+    });
+}
+
+#[test]
+fn calculates_aggregates_over_joins() {
+    run(async {
+        create_users("aggregate-join", &["Alice"]).await;
+        let user: User = User::query()
+            .r#where("email", "=", "aggregate-join-0@example.com")
+            .first()
+            .await
+            .unwrap()
+            .unwrap();
+
+        for title in ["First", "Second"] {
+            Post::query()
+                .insert::<u64, _>(vec![
+                    ("title", Value::from(title)),
+                    ("content", Value::from("...")),
+                    ("user_id", Value::from(user.id)),
+                ])
+                .await
+                .unwrap();
+        }
+
+        let title: Option<String> = User::query()
+            .join("posts", "users.id", "=", "posts.user_id")
+            .r#where("users.id", "=", user.id)
+            .min("posts.title")
+            .await
+            .unwrap();
+
+        assert_eq!(title.as_deref(), Some("First"));
+    });
+}
+
+#[test]
+fn groups_rows() {
+    run(async {
+        create_users("group", &["Alice", "Alice", "Bob", "Carol"]).await;
+
+        #[derive(Debug, Deserialize)]
+        struct NameCount {
+            name: String,
+            total: u64,
+        }
+
+        let query = || {
+            User::query()
+                .select(&["name"])
+                .select_raw("COUNT(*) AS total", vec![])
+                .r#where("email", "like", "group-%")
+                .group_by(&["name"])
+        };
+
+        let counts = query()
+            .having("total", ">", 1)
+            .get_as::<NameCount>()
+            .await
+            .unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].name, "Alice");
+        assert_eq!(counts[0].total, 2);
+
+        let rows = query()
+            .having_raw("COUNT(*) = ?", vec![Value::from(1)])
+            .order_by("name", "asc")
+            .get_as::<HashMap<String, Value>>()
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["name"], Value::from("Bob"));
+        assert_eq!(rows[1]["name"], Value::from("Carol"));
+
+        assert_eq!(query().count().await.unwrap(), 3);
+        assert_eq!(query().having("total", ">", 1).count().await.unwrap(), 1);
+    });
+}
+
+#[test]
+fn joins_tables() {
+    run(async {
+        create_users("join", &["Alice", "Bob"]).await;
+        let alice: User = User::query()
+            .r#where("email", "=", "join-0@example.com")
+            .first()
+            .await
+            .unwrap()
+            .unwrap();
+
+        for title in ["Draft", "Published"] {
+            Post::query()
+                .insert::<u64, _>(vec![
+                    ("title", Value::from(title)),
+                    ("content", Value::from("...")),
+                    ("user_id", Value::from(alice.id)),
+                ])
+                .await
+                .unwrap();
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Row {
+            name: String,
+            title: Option<String>,
+        }
+
+        let rows = User::query()
+            .select(&["users.name", "posts.title"])
+            .left_join_with("posts", |join| {
+                join.on("users.id", "=", "posts.user_id")
+                    .r#where("posts.title", "=", "Published")
+            })
+            .r#where("users.email", "like", "join-%")
+            .order_by("users.name", "asc")
+            .get_as::<Row>()
+            .await
+            .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].name, "Alice");
+        assert_eq!(rows[0].title.as_deref(), Some("Published"));
+        assert_eq!(rows[1].name, "Bob");
+        assert_eq!(rows[1].title, None);
+    });
+}
+
+#[test]
+fn filters_by_subqueries() {
+    run(async {
+        create_users("subquery", &["Alice", "Bob", "Carol"]).await;
+        let alice: User = User::query()
+            .r#where("email", "=", "subquery-0@example.com")
+            .first()
+            .await
+            .unwrap()
+            .unwrap();
+
+        Post::query()
+            .insert::<u64, _>(vec![
+                ("title", Value::from("Subqueries")),
+                ("content", Value::from("...")),
+                ("user_id", Value::from(alice.id)),
+            ])
+            .await
+            .unwrap();
+
+        let names = |query: ensemble::query::Builder| async {
+            query
+                .r#where("email", "like", "subquery-%")
+                .order_by("name", "asc")
+                .get::<User>()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|user| user.name)
+                .collect::<Vec<_>>()
+        };
+
+        let with_posts = User::query().where_in_sub("id", |query| {
+            query
+                .from("posts")
+                .select(&["user_id"])
+                .r#where("title", "=", "Subqueries")
+        });
+        assert_eq!(names(with_posts).await, ["Alice"]);
+
+        let without_posts = User::query().where_not_exists(|query| {
+            query
+                .from("posts")
+                .select_raw("1", vec![])
+                .where_column("posts.user_id", "=", "users.id")
+        });
+        assert_eq!(names(without_posts).await, ["Bob", "Carol"]);
+
+        let latest = User::query().where_sub("id", "=", |query| {
+            query
+                .from("users")
+                .select_raw("MAX(id)", vec![])
+                .r#where("email", "like", "subquery-%")
+        });
+        assert_eq!(names(latest).await, ["Carol"]);
+
+        let count = User::query()
+            .from_sub(
+                User::query()
+                    .select(&["name"])
+                    .r#where("email", "like", "subquery-%"),
+                "names",
+            )
+            .r#where("name", "!=", "Alice")
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    });
+}
+
+#[test]
+fn filters_by_relationships() {
+    run(async {
+        create_users("has", &["Alice", "Bob", "Carol"]).await;
+        let users = User::query()
+            .r#where("email", "like", "has-%")
+            .order_by("name", "asc")
+            .get::<User>()
+            .await
+            .unwrap();
+
+        for (user, titles) in users.iter().zip([&["Draft"][..], &["Hello", "World", "Again"], &[]]) {
+            for title in titles {
+                Post::query()
+                    .insert::<u64, _>(vec![
+                        ("title", Value::from(*title)),
+                        ("content", Value::from("...")),
+                        ("user_id", Value::from(user.id)),
+                    ])
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let names = |query: ensemble::query::Builder| async {
+            query
+                .r#where("email", "like", "has-%")
+                .order_by("name", "asc")
+                .get::<User>()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|user| user.name)
+                .collect::<Vec<_>>()
+        };
+
+        let with_drafts = User::query().where_has("posts", |query| query.r#where("title", "=", "Draft"));
+        assert_eq!(names(with_drafts).await, ["Alice"]);
+        assert_eq!(names(User::query().has("posts", ">=", 3)).await, ["Bob"]);
+        assert_eq!(names(User::query().has("posts", "<", 3)).await, ["Alice", "Carol"]);
+        assert_eq!(names(User::query().doesnt_have("posts")).await, ["Carol"]);
+    });
+}
+
+#[test]
+fn selects_relationship_aggregates() {
+    run(async {
+        create_users("with-count", &["Alice", "Bob"]).await;
+        let alice: User = User::query()
+            .r#where("email", "=", "with-count-0@example.com")
+            .first()
+            .await
+            .unwrap()
+            .unwrap();
+
+        for title in ["First", "Second"] {
+            Post::query()
+                .insert::<u64, _>(vec![
+                    ("title", Value::from(title)),
+                    ("content", Value::from("...")),
+                    ("user_id", Value::from(alice.id)),
+                ])
+                .await
+                .unwrap();
+        }
+
+        let users = User::query()
+            .with_count("posts")
+            .with_max("posts", "title")
+            .with_exists("posts")
+            .r#where("email", "like", "with-count-%")
+            .order_by("name", "asc")
+            .get::<User>()
+            .await
+            .unwrap();
+
+        assert_eq!(users[0].name, "Alice");
+        assert_eq!(users[0].posts_count, 2);
+        assert_eq!(users[0].posts_max_title.as_deref(), Some("Second"));
+        assert!(users[0].posts_exists);
+        assert_eq!(users[1].posts_count, 0);
+        assert_eq!(users[1].posts_max_title, None);
+        assert!(!users[1].posts_exists);
+        assert_eq!(users[1].json()["posts_count"], 0);
+    });
+}
+
+#[test]
+fn combines_queries_with_unions() {
+    run(async {
+        create_users("union", &["Alice", "Bob"]).await;
+        let bob: User = User::query()
+            .r#where("email", "=", "union-1@example.com")
+            .first()
+            .await
+            .unwrap()
+            .unwrap();
+
+        for title in ["Bob", "Union"] {
+            Post::query()
+                .insert::<u64, _>(vec![
+                    ("title", Value::from(title)),
+                    ("content", Value::from("...")),
+                    ("user_id", Value::from(bob.id)),
+                ])
+                .await
+                .unwrap();
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Label {
+            label: String,
+        }
+
+        let query = || {
+            User::query()
+                .select(&["name as label"])
+                .r#where("email", "like", "union-%")
+                .union(
+                    Post::query()
+                        .select(&["title as label"])
+                        .r#where("user_id", "=", bob.id),
+                )
+        };
+
+        let labels = query()
+            .order_by("label", "desc")
+            .limit(2)
+            .get_as::<Label>()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["Union", "Bob"]);
+
+        assert_eq!(query().count().await.unwrap(), 3);
+
+        let all = User::query()
+            .select(&["name as label"])
+            .r#where("email", "like", "union-%")
+            .union_all(
+                Post::query()
+                    .select(&["title as label"])
+                    .r#where("user_id", "=", bob.id),
+            );
+        assert_eq!(all.count().await.unwrap(), 4);
+    });
+}
+
+#[derive(Debug, Clone, Model)]
+struct Category {
+    id: u64,
+    name: String,
+    parent_id: Option<u64>,
+    #[model(foreign_key = "parent_id")]
+    children: HasMany<Category, Category>,
+    #[model(aggregate)]
+    children_count: u64,
+}
+
+/// Creates the given categories under the parent, returning their ids.
+async fn create_categories(names: &[&str], parent_id: Option<u64>) -> Vec<u64> {
+    let mut ids = vec![];
+    for name in names {
+        let category = Category {
+            name: (*name).to_string(),
+            parent_id,
+            ..Category::default()
+        }
+        .create()
+        .await
+        .unwrap();
+        ids.push(category.id);
+    }
+
+    ids
+}
+
+#[test]
+fn queries_the_existence_of_self_relations() {
+    run(async {
+        let parents = create_categories(&["Music", "Film"], None).await;
+        create_categories(&["Jazz", "Blues"], Some(parents[0])).await;
+        let query = || Category::query().where_in("id", parents.clone());
+
+        let names = |categories: Vec<Category>| {
+            categories
+                .into_iter()
+                .map(|category| category.name)
+                .collect::<Vec<_>>()
+        };
+
+        let with_children = query().has("children", ">=", 1).get().await.unwrap();
+        assert_eq!(names(with_children), ["Music"]);
+
+        let with_two = query().has("children", "=", 2).get().await.unwrap();
+        assert_eq!(names(with_two), ["Music"]);
+
+        let with_blues = query()
+            .where_has("children", |query| query.r#where("name", "=", "Blues"))
+            .get()
+            .await
+            .unwrap();
+        assert_eq!(names(with_blues), ["Music"]);
+
+        let childless = query().doesnt_have("children").get().await.unwrap();
+        assert_eq!(names(childless), ["Film"]);
+    });
+}
+
+#[test]
+fn counts_self_relations() {
+    run(async {
+        let parents = create_categories(&["Games", "Books"], None).await;
+        create_categories(&["Chess", "Go", "Poker"], Some(parents[0])).await;
+
+        let counts = Category::query()
+            .with_count("children")
+            .where_in("id", parents)
+            .order_by("id", "asc")
+            .get::<Category>()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|category| (category.name, category.children_count))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [("Games".to_string(), 3), ("Books".to_string(), 0)]);
+    });
+}
+
+#[test]
+fn loads_trees_with_recursive_ctes() {
+    run(async {
+        let create = |name: &'static str, parent_id: Option<u64>| async move {
+            Category {
+                name: name.to_string(),
+                parent_id,
+                ..Category::default()
+            }
+            .create()
+            .await
+            .unwrap()
+            .id
+        };
+
+        let root = create("Root", None).await;
+        let books = create("Books", Some(root)).await;
+        create("Fiction", Some(books)).await;
+        create("Unrelated", None).await;
+
+        let subtree = |id: u64| {
+            Category::query()
+                .with_recursive(
+                    "tree",
+                    Category::query().r#where("id", "=", id),
+                    Category::query()
+                        .select(&["categories.*"])
+                        .join("tree", "categories.parent_id", "=", "tree.id"),
+                )
+                .from("tree")
+                .order_by("id", "asc")
+        };
+
+        let names = subtree(root)
+            .get::<Category>()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|category| category.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Root", "Books", "Fiction"]);
+        assert_eq!(subtree(books).count().await.unwrap(), 2);
+
+        let named = Category::query()
+            .with_cte(
+                "roots",
+                Category::query().where_null("parent_id"),
+            )
+            .from("roots")
+            .r#where("name", "=", "Unrelated")
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(named, 1);
+    });
+}
+
+#[test]
+fn paginates_results() {
+    run(async {
+        create_users("paginate", &["A", "B", "C", "D", "E"]).await;
+        let query = || {
+            User::query()
+                .r#where("email", "like", "paginate-%")
+                .order_by("name", "desc")
+        };
+
+        let page = query().paginate::<User>(2, 2).await.unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.last_page, 3);
+        assert_eq!(
+            page.items.iter().map(|user| &user.name).collect::<Vec<_>>(),
+            ["C", "B"]
+        );
+
+        let json = serde_json::to_value(page.with_path("/users")).unwrap();
+        assert_eq!(json["links"]["next"], "/users?page=3");
+        assert_eq!(json["meta"]["from"], 3);
+
+        let page = query().simple_paginate::<User>(3, 2).await.unwrap();
+        assert!(!page.has_more_pages);
+        assert_eq!(page.items.len(), 1);
+        assert!(query().simple_paginate::<User>(2, 2).await.unwrap().has_more_pages);
+        assert!(matches!(
+            query().simple_paginate::<User>(1, u64::MAX).await,
+            Err(ensemble::Error::InvalidQuery)
+        ));
+
+        let empty = User::query()
+            .r#where("email", "=", "nobody@example.com")
+            .paginate::<User>(1, 10)
+            .await
+            .unwrap();
+        assert_eq!((empty.total, empty.last_page), (0, 1));
+        assert!(matches!(
+            User::query()
+                .r#where("email", "=", "nobody@example.com")
+                .paginate::<User>(1, 0)
+                .await,
+            Err(ensemble::Error::InvalidQuery)
+        ));
+
+        let grouped = User::query()
+            .select(&["name"])
+            .r#where("email", "like", "paginate-%")
+            .group_by(&["name"])
+            .paginate::<User>(1, 2)
+            .await
+            .unwrap();
+        assert_eq!((grouped.total, grouped.last_page), (5, 3));
+        assert_eq!(grouped.items.len(), 2);
+    });
+}
+
+#[test]
+fn chunks_results() {
+    run(async {
+        create_users("chunk", &["A", "B", "C", "D", "E"]).await;
+        let query = || User::query().r#where("email", "like", "chunk-%");
+
+        let mut sizes = vec![];
+        let completed = query()
+            .chunk(2, |users: Vec<User>| {
+                sizes.push(users.len());
+                async { Ok(true) }
+            })
+            .await
+            .unwrap();
+        assert!(completed);
+        assert_eq!(sizes, [2, 2, 1]);
+
+        let mut names = vec![];
+        let completed = query()
+            .each(2, |user: User| {
+                names.push(user.name);
+                let keep_going = names.len() < 3;
+                async move { Ok(keep_going) }
+            })
+            .await
+            .unwrap();
+        assert!(!completed);
+        assert_eq!(names, ["A", "B", "C"]);
+
+        // updating the rows removes them from the query, which would make an offset skip over some of them
+        query()
+            .r#where("is_admin", "=", false)
+            .chunk_by_id(2, |users: Vec<User>| async move {
+                for mut user in users {
+                    user.is_admin = true;
+                    user.save().await?;
+                }
+
+                Ok(true)
+            })
+            .await
+            .unwrap();
+        assert_eq!(query().r#where("is_admin", "=", false).count().await.unwrap(), 0);
+
+        let users = query()
+            .lazy_by_id::<User>(2)
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            users.iter().map(|user| &user.name).collect::<Vec<_>>(),
+            ["A", "B", "C", "D", "E"]
+        );
+
+        assert!(query().lazy_by_id::<User>(0).is_err());
+
+        // the primary key is qualified, so it isn't ambiguous with the joined table's
+        for user in &users {
+            Post::query()
+                .insert::<u64, _>(vec![
+                    ("title", Value::from(user.name.as_str())),
+                    ("content", Value::from("...")),
+                    ("user_id", Value::from(user.id)),
+                ])
+                .await
+                .unwrap();
+        }
+
+        let names = query()
+            .select(&["users.*"])
+            .join("posts", "users.id", "=", "posts.user_id")
+            .lazy_by_id::<User>(2)
+            .unwrap()
+            .map_ok(|user| user.name)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names, ["A", "B", "C", "D", "E"]);
+    });
+}
+
+#[test]
+fn chunks_by_id_without_selecting_the_key() {
+    run(async {
+        create_users("unkeyed", &["A", "B", "C"]).await;
+
+        let names = User::query()
+            .select(&["name"])
+            .r#where("email", "like", "unkeyed-%")
+            .lazy_by_id::<User>(2)
+            .unwrap()
+            .map_ok(|user| user.name)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names, ["A", "B", "C"]);
+    });
+}
+
+#[test]
+fn cursor_paginates_results() {
+    run(async {
+        create_users("cursor", &["A", "B", "B", "C", "C", "D"]).await;
+        let query = || {
+            User::query()
+                .r#where("email", "like", "cursor-%")
+                .order_by("name", "desc")
+        };
+        let emails = |page: &ensemble::pagination::CursorPaginator<User>| {
+            page.items
+                .iter()
+                .map(|user| user.email.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let first = query().cursor_paginate::<User>(2, None).await.unwrap();
+        assert_eq!(emails(&first), ["cursor-5@example.com", "cursor-3@example.com"]);
+        assert!(first.prev_cursor.is_none());
+
+        let second = query()
+            .cursor_paginate::<User>(2, first.next_cursor.clone())
+            .await
+            .unwrap();
+        assert_eq!(emails(&second), ["cursor-4@example.com", "cursor-1@example.com"]);
+
+        let last = query()
+            .cursor_paginate::<User>(2, second.next_cursor.clone())
+            .await
+            .unwrap();
+        assert_eq!(emails(&last), ["cursor-2@example.com", "cursor-0@example.com"]);
+        assert!(last.next_cursor.is_none());
+
+        let encoded = last.prev_cursor.unwrap().encode();
+        let back = query()
+            .cursor_paginate::<User>(2, Some(encoded.parse().unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(emails(&back), emails(&second));
+        assert_eq!(back.next_cursor, second.next_cursor);
+
+        let first_again = query()
+            .cursor_paginate::<User>(2, back.prev_cursor)
+            .await
+            .unwrap();
+        assert_eq!(emails(&first_again), emails(&first));
+        assert!(first_again.prev_cursor.is_none());
+
+        // timestamps are bound with their type, so they compare like the column does
+        let by_date = || query().order_by("created_at", "desc");
+        let first = by_date().cursor_paginate::<User>(4, None).await.unwrap();
+        assert!(matches!(
+            first.next_cursor.as_ref().unwrap().parameter("created_at"),
+            Some(Value::Ext("DateTime", _))
+        ));
+
+        let encoded = first.next_cursor.unwrap().encode();
+        let rest = by_date()
+            .cursor_paginate::<User>(4, Some(encoded.parse().unwrap()))
+            .await
+            .unwrap();
+        let mut ids = first
+            .items
+            .iter()
+            .chain(&rest.items)
+            .map(|user| user.id)
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 6);
+    });
+}
@@ -0,0 +1,43 @@
+use ensemble::{types::DateTime, Model};
+
+use crate::run;
+
+#[derive(Debug, Clone, Model)]
+#[ensemble(connection = "replicated")]
+struct Event {
+    id: u64,
+    name: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+#[test]
+fn reads_go_to_replicas_and_writes_to_the_primary() {
+    run(async {
+        Event::create(Event {
+            name: "signup".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert!(Event::query().count().await.is_err());
+        assert!(Event::query().use_write_connection().count().await.unwrap() >= 1);
+    });
+}
+
+#[test]
+fn sticky_scopes_read_from_the_primary_after_writing() {
+    run(ensemble::sticky(async {
+        assert!(Event::query().count().await.is_err());
+
+        Event::create(Event {
+            name: "login".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert!(Event::query().count().await.unwrap() >= 1);
+    }));
+}
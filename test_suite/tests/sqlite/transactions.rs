@@ -0,0 +1,205 @@
+use ensemble::{types::DateTime, DatabaseError, Error, Model};
+use futures::future::{self, Either};
+use std::pin::pin;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::run;
+
+/// SQLite only allows one writer at a time, so the transaction tests take turns.
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Model)]
+#[ensemble(connection = "transactional")]
+struct Event {
+    id: u64,
+    name: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+}
+
+async fn create(name: &str) -> Result<Event, Error> {
+    Event::create(Event {
+        name: name.to_string(),
+        ..Default::default()
+    })
+    .await
+}
+
+async fn count(name: &str) -> u64 {
+    Event::query()
+        .r#where("name", "=", name)
+        .count()
+        .await
+        .unwrap()
+}
+
+#[test]
+fn commits_when_the_closure_succeeds() {
+    run(async {
+        let _lock = LOCK.lock().await;
+
+        let event = ensemble::transaction_on("transactional", || async {
+            let event = create("committed").await?;
+
+            // reads inside the transaction see its uncommitted writes.
+            assert_eq!(Event::find(event.id).await?.name, "committed");
+
+            Ok::<_, Error>(event)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(Event::find(event.id).await.unwrap().name, "committed");
+    });
+}
+
+#[test]
+fn rolls_back_when_the_closure_fails() {
+    run(async {
+        let _lock = LOCK.lock().await;
+
+        let result = ensemble::transaction_on("transactional", || async {
+            create("failed").await?;
+
+            Err::<(), _>(Error::NotFound)
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::NotFound)));
+        assert_eq!(count("failed").await, 0);
+    });
+}
+
+#[test]
+fn rolls_back_when_the_closure_panics() {
+    run(async {
+        let _lock = LOCK.lock().await;
+
+        let result = tokio::spawn(ensemble::transaction_on("transactional", || async {
+            create("panicked").await?;
+
+            panic!("something went wrong");
+
+            #[allow(unreachable_code)]
+            Ok::<_, Error>(())
+        }))
+        .await;
+
+        assert!(result.unwrap_err().is_panic());
+        assert_eq!(count("panicked").await, 0);
+    });
+}
+
+#[test]
+fn nested_transactions_roll_back_to_their_savepoint() {
+    run(async {
+        let _lock = LOCK.lock().await;
+
+        ensemble::transaction_on("transactional", || async {
+            create("outer").await?;
+
+            let inner = ensemble::transaction_on("transactional", || async {
+                create("inner").await?;
+
+                Err::<(), _>(Error::NotFound)
+            })
+            .await;
+            assert!(inner.is_err());
+
+            ensemble::transaction_on("transactional", || async {
+                create("sibling").await.map(|_| ())
+            })
+            .await
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(count("outer").await, 1);
+        assert_eq!(count("inner").await, 0);
+        assert_eq!(count("sibling").await, 1);
+    });
+}
+
+#[test]
+fn cancelled_nested_transactions_roll_back_to_their_savepoint() {
+    run(async {
+        let _lock = LOCK.lock().await;
+
+        ensemble::transaction_on("transactional", || async {
+            create("outer-of-cancelled").await?;
+
+            let (inserted, wait_for_insert) = oneshot::channel();
+            let inner = ensemble::transaction_on("transactional", || async {
+                create("cancelled").await?;
+                inserted.send(()).unwrap();
+
+                future::pending::<Result<(), Error>>().await
+            });
+
+            // the inner transaction is dropped as soon as it has inserted its row
+            let Either::Right(_) = future::select(pin!(inner), wait_for_insert).await else {
+                panic!("the inner transaction should never finish");
+            };
+
+            Ok::<_, Error>(())
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(count("outer-of-cancelled").await, 1);
+        assert_eq!(count("cancelled").await, 0);
+    });
+}
+
+#[test]
+fn retries_transactions_that_fail_with_transient_errors() {
+    run(async {
+        let _lock = LOCK.lock().await;
+        let mut attempts = 0;
+
+        ensemble::retry_transaction_on("transactional", || {
+            attempts += 1;
+            let attempt = attempts;
+
+            async move {
+                create("retried").await?;
+
+                if attempt == 1 {
+                    return Err(Error::Deadlock {
+                        details: Box::new(DatabaseError {
+                            sqlstate: Some("40001".to_string()),
+                            message: "Deadlock found when trying to get lock".to_string(),
+                            sql: "insert into events (name) values (?)".to_string(),
+                            bindings: vec![],
+                        }),
+                    });
+                }
+
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(attempts, 2);
+        assert_eq!(count("retried").await, 1);
+    });
+}
+
+#[test]
+fn does_not_retry_transactions_that_fail_with_other_errors() {
+    run(async {
+        let _lock = LOCK.lock().await;
+        let mut attempts = 0;
+
+        let result = ensemble::retry_transaction_on("transactional", || {
+            attempts += 1;
+
+            async { Err::<(), _>(Error::NotFound) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(Error::NotFound)));
+        assert_eq!(attempts, 1);
+    });
+}